@@ -0,0 +1,19 @@
+//! Writes the JSON Schema of each top-level type to `schema/`.
+
+use std::{fs, path::Path};
+
+use apple_continuity::schema::{schemas, SCHEMA_VERSION};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("schema");
+    fs::create_dir_all(&directory)?;
+
+    for (name, mut schema) in schemas() {
+        schema.insert("x-schema-version".to_string(), SCHEMA_VERSION.into());
+        let path = directory.join(format!("{}.json", name));
+        fs::write(&path, serde_json::to_string_pretty(&schema)? + "\n")?;
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{messages::Message, Error, ErrorKind};
+
+pub const APPLE_COMPANY_ID: u16 = 0x004C;
+
+/// The most advertising data a legacy advertising or scan response PDU carries.
+pub const MAX_ADVERTISING_DATA_LENGTH: usize = 31;
+
+const AD_TYPE_MANUFACTURER_DATA: u8 = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address(pub [u8; 6]);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PduType {
+    AdvInd = 0x00,
+    AdvDirectInd = 0x01,
+    AdvNonconnInd = 0x02,
+    ScanReq = 0x03,
+    ScanRsp = 0x04,
+    ConnectInd = 0x05,
+    AdvScanInd = 0x06,
+    Unknown = 0xFF,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Advertisement {
+    pub timestamp: Duration,
+    pub address: Address,
+    pub random_address: bool,
+    pub pdu_type: PduType,
+    pub rssi: Option<i8>,
+    pub channel: Option<u8>,
+    #[serde(with = "crate::hex")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub data: Vec<u8>,
+}
+
+impl Address {
+    /// Builds an address from the little-endian byte order used over the air and in HCI.
+    pub fn from_le_bytes(bytes: &[u8]) -> Address {
+        let mut address = [0; 6];
+        for (i, byte) in bytes.iter().take(6).enumerate() {
+            address[5 - i] = *byte;
+        }
+
+        Address(address)
+    }
+
+    pub fn to_le_bytes(&self) -> [u8; 6] {
+        let mut bytes = self.0;
+        bytes.reverse();
+        bytes
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+            self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5]
+        )
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut address = [0; 6];
+        let mut parts = s.split([':', '-']);
+
+        for byte in address.iter_mut() {
+            *byte = parts
+                .next()
+                .and_then(|part| u8::from_str_radix(part, 16).ok())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::DecodeError,
+                        format!("Invalid address: {}", s).as_str(),
+                    )
+                })?;
+        }
+
+        if parts.next().is_some() {
+            return Err(Error::new(
+                ErrorKind::DecodeError,
+                format!("Invalid address: {}", s).as_str(),
+            ));
+        }
+
+        Ok(Address(address))
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Address::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for Address {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Address".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": "^[0-9A-Fa-f]{2}([:-][0-9A-Fa-f]{2}){5}$"
+        })
+    }
+}
+
+impl Display for PduType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PduType::AdvInd => "ADV_IND",
+                PduType::AdvDirectInd => "ADV_DIRECT_IND",
+                PduType::AdvNonconnInd => "ADV_NONCONN_IND",
+                PduType::ScanReq => "SCAN_REQ",
+                PduType::ScanRsp => "SCAN_RSP",
+                PduType::ConnectInd => "CONNECT_IND",
+                PduType::AdvScanInd => "ADV_SCAN_IND",
+                PduType::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl From<u8> for PduType {
+    fn from(value: u8) -> Self {
+        match value & 0x0F {
+            0x00 => PduType::AdvInd,
+            0x01 => PduType::AdvDirectInd,
+            0x02 => PduType::AdvNonconnInd,
+            0x03 => PduType::ScanReq,
+            0x04 => PduType::ScanRsp,
+            0x05 => PduType::ConnectInd,
+            0x06 => PduType::AdvScanInd,
+            _ => PduType::Unknown,
+        }
+    }
+}
+
+impl PduType {
+    /// Whether the PDU payload is an advertiser address followed by advertising data.
+    pub fn has_adv_data(&self) -> bool {
+        matches!(
+            self,
+            PduType::AdvInd | PduType::AdvNonconnInd | PduType::ScanRsp | PduType::AdvScanInd
+        )
+    }
+}
+
+impl Advertisement {
+    /// Builds an advertisement carrying `messages` as Apple manufacturer data.
+    pub fn from_messages(
+        timestamp: Duration,
+        address: Address,
+        messages: &[Message],
+    ) -> Result<Advertisement, Error> {
+        let mut manufacturer_data = APPLE_COMPANY_ID.to_le_bytes().to_vec();
+        for message in messages {
+            manufacturer_data.extend(message.encode());
+        }
+
+        // The structure adds its length and AD type bytes to the manufacturer data
+        if manufacturer_data.len() + 2 > MAX_ADVERTISING_DATA_LENGTH {
+            return Err(Error::new(
+                ErrorKind::EncodeError,
+                format!(
+                    "Messages take {} bytes of advertising data, more than {}",
+                    manufacturer_data.len() + 2,
+                    MAX_ADVERTISING_DATA_LENGTH
+                )
+                .as_str(),
+            ));
+        }
+
+        let mut data = vec![manufacturer_data.len() as u8 + 1, AD_TYPE_MANUFACTURER_DATA];
+        data.extend(manufacturer_data);
+
+        Ok(Advertisement {
+            timestamp,
+            address,
+            random_address: true,
+            pdu_type: PduType::AdvInd,
+            rssi: None,
+            channel: None,
+            data,
+        })
+    }
+
+    /// Returns the body of the Apple manufacturer specific data, with the company ID stripped.
+    pub fn manufacturer_data(&self) -> Option<&[u8]> {
+        manufacturer_data(&self.data)
+    }
+
+    /// Decodes every Continuity message in the manufacturer data, skipping unknown opcodes.
+    pub fn messages(&self) -> Vec<Message> {
+        match self.manufacturer_data() {
+            Some(data) => split_messages(data)
+                .filter_map(|data| Message::decode(data).ok())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Finds the Apple manufacturer specific data in a sequence of advertising data structures and
+/// returns its body, with the company ID stripped.
+pub fn manufacturer_data(data: &[u8]) -> Option<&[u8]> {
+    let mut offset = 0;
+    while offset < data.len() {
+        let length = data[offset] as usize;
+        if length == 0 || offset + 1 + length > data.len() {
+            break;
+        }
+
+        let ad_type = data[offset + 1];
+        let body = &data[offset + 2..offset + 1 + length];
+        if ad_type == AD_TYPE_MANUFACTURER_DATA
+            && body.len() >= 2
+            && u16::from_le_bytes([body[0], body[1]]) == APPLE_COMPANY_ID
+        {
+            return Some(&body[2..]);
+        }
+
+        offset += 1 + length;
+    }
+
+    None
+}
+
+/// Splits Continuity manufacturer data into its individual `opcode, length, body` messages.
+pub fn split_messages(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset + 2 > data.len() {
+            return None;
+        }
+
+        let end = offset + 2 + data[offset + 1] as usize;
+        if end > data.len() {
+            return None;
+        }
+
+        let message = &data[offset..end];
+        offset = end;
+        Some(message)
+    })
+}
@@ -0,0 +1,88 @@
+use std::{fmt::Display, net::Ipv4Addr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::messages::AirplayTargetMessage;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AirPlayFlags {
+    pub screen_mirroring: bool,
+    pub password_required: bool,
+    pub pin_required: bool,
+    /// Only devices on the same network or in the home can stream to the receiver.
+    pub access_control: bool,
+    /// The undecoded byte, for the bits not covered above.
+    pub raw: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AirPlayReceiver {
+    pub ipv4_address: Ipv4Addr,
+    pub flags: AirPlayFlags,
+    /// Changes whenever the receiver's configuration changes, telling clients to refresh what
+    /// they know about it over the network.
+    pub seed: u8,
+}
+
+impl From<u8> for AirPlayFlags {
+    fn from(value: u8) -> Self {
+        AirPlayFlags {
+            screen_mirroring: value & 0x01 != 0,
+            password_required: value & 0x02 != 0,
+            pin_required: value & 0x04 != 0,
+            access_control: value & 0x08 != 0,
+            raw: value,
+        }
+    }
+}
+
+impl AirPlayReceiver {
+    /// Whether the receiver's configuration changed since `previous` was seen.
+    pub fn config_changed(&self, previous: &AirPlayReceiver) -> bool {
+        self.seed != previous.seed
+    }
+}
+
+impl From<AirplayTargetMessage> for AirPlayReceiver {
+    fn from(value: AirplayTargetMessage) -> Self {
+        AirPlayReceiver {
+            ipv4_address: Ipv4Addr::from(value.ip4_address),
+            flags: AirPlayFlags::from(value.flags),
+            seed: value.seed,
+        }
+    }
+}
+
+impl Display for AirPlayFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags: Vec<&str> = [
+            (self.screen_mirroring, "screen mirroring"),
+            (self.password_required, "password"),
+            (self.pin_required, "PIN"),
+            (self.access_control, "access control"),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| name)
+        .collect();
+
+        if flags.is_empty() {
+            write!(f, "no flags")
+        } else {
+            write!(f, "{}", flags.join(", "))
+        }
+    }
+}
+
+impl Display for AirPlayReceiver {
+    /// For example `192.168.1.2, screen mirroring, password, seed 05`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, {}, seed {:02X}",
+            self.ipv4_address, self.flags, self.seed
+        )
+    }
+}
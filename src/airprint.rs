@@ -1,8 +1,19 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
 use serde::{Deserialize, Serialize};
 
-use crate::messages::AirPrintMessage;
+use crate::{messages::AirPrintMessage, Error, ErrorKind};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum AddressType {
     IPv4 = 0x01,
     IPv6 = 0x02,
@@ -10,6 +21,8 @@ pub enum AddressType {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum ResourceType {
     ResourcePath = 0x00,
     PrinterId = 0x01,
@@ -17,12 +30,19 @@ pub enum ResourceType {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum SecurityType {
     None = 0x00,
     TLS = 0x01,
     Unknown = 0xFF,
 }
 
+const IPP_PORT: u16 = 631;
+
+// IPP Everywhere printers are required to serve this path
+pub const DEFAULT_RESOURCE_PATH: &str = "/ipp/print";
+
 const RESOURCE_PATH_VALUES: [&str; 7] = [
     "/ipp/print",
     "/ipp/printer",
@@ -33,15 +53,39 @@ const RESOURCE_PATH_VALUES: [&str; 7] = [
     "/printer",
 ];
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PrinterEntry {
+    pub vendor: String,
+    pub model: Option<String>,
+    pub resource_path: Option<String>,
+}
+
+/// Maps the resource path indexes and printer IDs advertised by printers to paths and vendors.
+/// The default catalog holds the built-in resource paths and no printer IDs. The installed
+/// catalog is used wherever no catalog is passed, such as `Printer::from` and dissection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ResourceCatalog {
+    #[serde(default)]
+    pub resource_paths: BTreeMap<u16, String>,
+    #[serde(default)]
+    pub printers: BTreeMap<u16, PrinterEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Printer {
     pub address_type: AddressType,
     pub resource_type: ResourceType,
     pub security_type: SecurityType,
     pub printer_id: Option<u16>,
     pub resource_path: Option<String>,
+    /// The vendor of a printer ID found in the catalog.
+    pub vendor: Option<String>,
     pub port: u16,
-    pub ipv4_address: Option<u32>,
-    pub ipv6_address: Option<u128>,
+    pub ipv4_address: Option<Ipv4Addr>,
+    pub ipv6_address: Option<Ipv6Addr>,
     pub measured_power: u8,
 }
 
@@ -75,39 +119,262 @@ impl From<u8> for SecurityType {
     }
 }
 
-impl From<AirPrintMessage> for Printer {
-    fn from(value: AirPrintMessage) -> Self {
+impl Default for ResourceCatalog {
+    fn default() -> Self {
+        ResourceCatalog {
+            resource_paths: RESOURCE_PATH_VALUES
+                .iter()
+                .enumerate()
+                .map(|(index, path)| (index as u16, path.to_string()))
+                .collect(),
+            printers: BTreeMap::new(),
+        }
+    }
+}
+
+fn global() -> &'static RwLock<ResourceCatalog> {
+    static GLOBAL: OnceLock<RwLock<ResourceCatalog>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(ResourceCatalog::default()))
+}
+
+impl ResourceCatalog {
+    /// A catalog with no entries, not even the built-in resource paths.
+    pub fn empty() -> ResourceCatalog {
+        ResourceCatalog {
+            resource_paths: BTreeMap::new(),
+            printers: BTreeMap::new(),
+        }
+    }
+
+    /// A copy of the installed catalog.
+    pub fn current() -> ResourceCatalog {
+        global()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Makes `Printer::from`, and everything resolving printers without a catalog of its own,
+    /// use this catalog.
+    pub fn install(self) {
+        *global().write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// Calls `f` with the installed catalog.
+    pub(crate) fn with_installed<T>(f: impl FnOnce(&ResourceCatalog) -> T) -> T {
+        f(&global().read().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Parses a catalog from JSON of the form
+    /// `{"resource_paths": {"7": "/ipp"}, "printers": {"4660": {"vendor": "..."}}}`.
+    pub fn from_json(json: &str) -> Result<ResourceCatalog, Error> {
+        serde_json::from_str(json).map_err(|error| {
+            Error::new(
+                ErrorKind::DecodeError,
+                format!("Invalid resource catalog: {}", error).as_str(),
+            )
+        })
+    }
+
+    /// Loads the entries in a JSON catalog file on top of this catalog's, replacing any with
+    /// the same index or ID.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let catalog = ResourceCatalog::from_json(&fs::read_to_string(path)?)?;
+        self.extend(catalog);
+        Ok(())
+    }
+
+    pub fn extend(&mut self, other: ResourceCatalog) {
+        self.resource_paths.extend(other.resource_paths);
+        self.printers.extend(other.printers);
+    }
+
+    pub fn register_path(&mut self, index: u16, path: &str) {
+        self.resource_paths.insert(index, path.to_string());
+    }
+
+    pub fn register_printer(&mut self, id: u16, entry: PrinterEntry) {
+        self.printers.insert(id, entry);
+    }
+
+    pub fn resource_path(&self, index: u16) -> Option<&str> {
+        self.resource_paths.get(&index).map(String::as_str)
+    }
+
+    pub fn printer(&self, id: u16) -> Option<&PrinterEntry> {
+        self.printers.get(&id)
+    }
+}
+
+impl Printer {
+    /// Interprets a message, resolving its resource path index or printer ID with `catalog`.
+    pub fn from_message(value: AirPrintMessage, catalog: &ResourceCatalog) -> Printer {
         let address_type = AddressType::from(value.connection_info);
         let resource_type = ResourceType::from(value.connection_info);
 
+        let (printer_id, resource_path, vendor) = match resource_type {
+            ResourceType::ResourcePath => (
+                None,
+                catalog
+                    .resource_path(value.rp_index_value)
+                    .map(str::to_string),
+                None,
+            ),
+            ResourceType::PrinterId => {
+                let entry = catalog.printer(value.rp_index_value);
+                (
+                    Some(value.rp_index_value),
+                    entry.and_then(|entry| entry.resource_path.clone()),
+                    entry.map(|entry| entry.vendor.clone()),
+                )
+            }
+            ResourceType::Unknown => (None, None, None),
+        };
+
         Printer {
             address_type,
             resource_type,
             security_type: SecurityType::from(value.connection_info),
-            printer_id: match resource_type {
-                ResourceType::PrinterId => Some(value.rp_index_value),
-                _ => None,
-            },
-            resource_path: match resource_type {
-                ResourceType::ResourcePath => {
-                    if (value.rp_index_value as usize) < RESOURCE_PATH_VALUES.len() {
-                        Some(RESOURCE_PATH_VALUES[value.rp_index_value as usize].to_string())
-                    } else {
-                        None
-                    }
-                }
-                _ => None,
-            },
+            printer_id,
+            resource_path,
+            vendor,
             ipv4_address: match address_type {
-                AddressType::IPv4 => Some(value.ip4_address),
+                AddressType::IPv4 => Some(Ipv4Addr::new(
+                    value.address[0],
+                    value.address[1],
+                    value.address[2],
+                    value.address[3],
+                )),
                 _ => None,
             },
             ipv6_address: match address_type {
-                AddressType::IPv6 => Some((value.ip4_address as u128) << 96 + value.ip6_address),
+                AddressType::IPv6 => Some(Ipv6Addr::from(value.address)),
                 _ => None,
             },
             port: value.port,
             measured_power: value.measured_power,
         }
     }
+
+    /// The RSSI at 1m advertised by the printer.
+    pub fn tx_power(&self) -> i8 {
+        self.measured_power as i8
+    }
+
+    pub fn ip_addr(&self) -> Option<IpAddr> {
+        match (self.ipv4_address, self.ipv6_address) {
+            (Some(address), _) => Some(IpAddr::V4(address)),
+            (_, Some(address)) => Some(IpAddr::V6(address)),
+            _ => None,
+        }
+    }
+
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        self.ip_addr()
+            .map(|address| SocketAddr::new(address, self.port))
+    }
+
+    /// The path the printer serves IPP on: its resolved resource path, or
+    /// `DEFAULT_RESOURCE_PATH` for printers advertising a printer ID without a catalog path.
+    /// `None` when the resource path index isn't in the catalog or the resource type is unknown.
+    pub fn ipp_path(&self) -> Option<&str> {
+        match (self.resource_type, self.resource_path.as_deref()) {
+            (_, Some(path)) => Some(path),
+            (ResourceType::PrinterId, None) => Some(DEFAULT_RESOURCE_PATH),
+            _ => None,
+        }
+    }
+
+    /// The `ipp://` or `ipps://` URI of the printer, `None` without an address or `ipp_path`.
+    pub fn uri(&self) -> Option<String> {
+        let scheme = match self.security_type {
+            SecurityType::TLS => "ipps",
+            _ => "ipp",
+        };
+
+        let host = match self.ip_addr()? {
+            IpAddr::V4(address) => address.to_string(),
+            IpAddr::V6(address) => format!("[{}]", address),
+        };
+
+        let port = if self.port == IPP_PORT {
+            String::new()
+        } else {
+            format!(":{}", self.port)
+        };
+
+        Some(format!("{}://{}{}{}", scheme, host, port, self.ipp_path()?))
+    }
+}
+
+impl Display for AddressType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                AddressType::IPv4 => "IPv4",
+                AddressType::IPv6 => "IPv6",
+                AddressType::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl Display for ResourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ResourceType::ResourcePath => "resource path",
+                ResourceType::PrinterId => "printer ID",
+                ResourceType::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl Display for SecurityType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SecurityType::None => "no security",
+                SecurityType::TLS => "TLS",
+                SecurityType::Unknown => "unknown security",
+            }
+        )
+    }
+}
+
+impl Display for Printer {
+    /// For example `ipps://192.168.1.23/ipp/print, TLS, measured power -59 dBm`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.uri(), self.socket_addr()) {
+            (Some(uri), _) => write!(f, "{}", uri)?,
+            (None, Some(address)) => write!(f, "{}, unknown resource path", address)?,
+            (None, None) => write!(f, "unknown address")?,
+        }
+
+        match (&self.vendor, self.printer_id) {
+            (Some(vendor), _) => write!(f, " ({})", vendor)?,
+            (None, Some(printer_id)) => write!(f, " (printer ID {})", printer_id)?,
+            _ => {}
+        }
+
+        write!(
+            f,
+            ", {}, measured power {} dBm",
+            self.security_type,
+            self.tx_power()
+        )
+    }
+}
+
+impl From<AirPrintMessage> for Printer {
+    fn from(value: AirPrintMessage) -> Self {
+        ResourceCatalog::with_installed(|catalog| Printer::from_message(value, catalog))
+    }
 }
@@ -0,0 +1,193 @@
+use std::{collections::VecDeque, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::Component,
+    proximity::{DeviceShape, ProximityDevice},
+};
+
+// Levels are reported as a nibble multiplied by 10, so each value covers a 10% bucket
+const STEP: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatterySample {
+    pub timestamp: Duration,
+    pub level: u32,
+    pub charging: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatteryEstimate {
+    /// The last reported level.
+    pub level: u32,
+    /// The level extrapolated within the current 10% bucket using the estimated rate.
+    pub smoothed_level: f64,
+    /// Percent per hour, negative while discharging. `None` until the level has stepped at
+    /// least twice in the same direction.
+    pub rate: Option<f64>,
+    pub time_to_empty: Option<Duration>,
+    pub time_to_full: Option<Duration>,
+}
+
+/// A time series of left, right and case, or headphones, battery levels for one device.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct BatteryHistory {
+    max_age: Duration,
+    left: VecDeque<BatterySample>,
+    right: VecDeque<BatterySample>,
+    case: VecDeque<BatterySample>,
+    headphones: VecDeque<BatterySample>,
+}
+
+struct Step {
+    timestamp: f64,
+    boundary: f64,
+    rising: bool,
+}
+
+impl Default for BatteryHistory {
+    fn default() -> Self {
+        BatteryHistory::new(Duration::from_secs(4 * 60 * 60))
+    }
+}
+
+impl BatteryHistory {
+    /// Creates a history keeping samples for `max_age`.
+    pub fn new(max_age: Duration) -> BatteryHistory {
+        BatteryHistory {
+            max_age,
+            left: VecDeque::new(),
+            right: VecDeque::new(),
+            case: VecDeque::new(),
+            headphones: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, timestamp: Duration, device: &ProximityDevice) {
+        let max_age = self.max_age;
+        let (left, right, case, headphones) = match &device.shape {
+            DeviceShape::Earbuds(earbuds) => (
+                (earbuds.battery.left, earbuds.charging_state.left),
+                (earbuds.battery.right, earbuds.charging_state.right),
+                (earbuds.battery.case, earbuds.charging_state.case),
+                (None, false),
+            ),
+            DeviceShape::Headphones(headphones) => (
+                (None, false),
+                (None, false),
+                (None, false),
+                (headphones.battery, headphones.charging),
+            ),
+        };
+
+        for (samples, (level, charging)) in [
+            (&mut self.left, left),
+            (&mut self.right, right),
+            (&mut self.case, case),
+            (&mut self.headphones, headphones),
+        ] {
+            if let Some(level) = level {
+                samples.push_back(BatterySample {
+                    timestamp,
+                    level,
+                    charging,
+                });
+            }
+
+            while samples
+                .front()
+                .is_some_and(|sample| timestamp.saturating_sub(sample.timestamp) > max_age)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    pub fn samples(&self, component: Component) -> &VecDeque<BatterySample> {
+        match component {
+            Component::Left => &self.left,
+            Component::Right => &self.right,
+            Component::Case => &self.case,
+            Component::Headphones => &self.headphones,
+        }
+    }
+
+    /// Estimates the charge or discharge rate of a component from the times its level stepped
+    /// between buckets, rather than from the raw levels which only change every 10%.
+    pub fn estimate(&self, component: Component) -> Option<BatteryEstimate> {
+        let samples = self.samples(component);
+        let last = samples.back()?;
+        let now = last.timestamp.as_secs_f64();
+
+        // Only the latest run with the same charging state describes the current rate
+        let run_start = samples
+            .iter()
+            .rposition(|sample| sample.charging != last.charging)
+            .map(|index| index + 1)
+            .unwrap_or(0);
+
+        let steps: Vec<Step> = samples
+            .range(run_start..)
+            .zip(samples.range(run_start + 1..))
+            .filter(|(a, b)| a.level != b.level)
+            .map(|(a, b)| Step {
+                // The level crossed a bucket boundary somewhere between the two samples
+                timestamp: (a.timestamp.as_secs_f64() + b.timestamp.as_secs_f64()) / 2.0,
+                boundary: a.level.max(b.level) as f64,
+                rising: b.level > a.level,
+            })
+            .collect();
+
+        let latest = steps.last();
+        let first = latest.and_then(|latest| {
+            steps
+                .iter()
+                .rev()
+                .take_while(|step| step.rising == latest.rising)
+                .last()
+        });
+
+        let rate = match (first, latest) {
+            (Some(first), Some(latest)) if latest.timestamp > first.timestamp => {
+                let mut rate =
+                    (latest.boundary - first.boundary) / (latest.timestamp - first.timestamp);
+
+                // No step since the last one bounds how fast the level can be moving
+                let elapsed = now - latest.timestamp;
+                if elapsed > 0.0 && rate.abs() * elapsed > STEP {
+                    rate = STEP / elapsed * rate.signum();
+                }
+
+                Some(rate)
+            }
+            _ => None,
+        };
+
+        let level = last.level as f64;
+        let ceiling = (level + STEP).min(100.0);
+        let smoothed_level = match (latest, rate) {
+            (Some(latest), Some(rate)) => {
+                (latest.boundary + rate * (now - latest.timestamp)).clamp(level, ceiling)
+            }
+            _ => (level + ceiling) / 2.0,
+        };
+
+        let rate = rate.map(|rate| rate * 3600.0);
+
+        Some(BatteryEstimate {
+            level: last.level,
+            smoothed_level,
+            rate,
+            time_to_empty: rate
+                .filter(|rate| *rate < 0.0)
+                .map(|rate| Duration::from_secs_f64(smoothed_level / -rate * 3600.0)),
+            time_to_full: rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Duration::from_secs_f64((100.0 - smoothed_level) / rate * 3600.0)),
+        })
+    }
+}
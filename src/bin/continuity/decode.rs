@@ -0,0 +1,175 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+};
+
+use apple_continuity::{
+    advertisement::{manufacturer_data, split_messages, APPLE_COMPANY_ID},
+    hex,
+    messages::Message,
+    Error, ErrorKind,
+};
+use serde_json::json;
+
+use crate::output::{install_catalog, message_json};
+
+pub const USAGE: &str = "Usage: continuity [decode] [OPTIONS] [FILE]
+
+Decodes Apple Continuity advertisements given as hex strings, one per line. Each line
+may be a single message, Continuity manufacturer data with or without the 4C00 company
+ID, or a complete advertising data structure. Reads from stdin when FILE is omitted.
+
+Options:
+    --json      Print one JSON object per message instead of a table
+    --dump      Print an annotated hex dump of each message below its row
+    --dissect   Print each message's field tree below its row, or add it to the JSON
+    --catalog CATALOG
+                Resolve AirPrint resource paths and printer IDs with the entries in this
+                JSON catalog as well as the built-in ones
+    -h, --help  Print this help";
+
+#[derive(Debug, Default)]
+pub struct Options {
+    json: bool,
+    dump: bool,
+    dissect: bool,
+    catalog: Option<String>,
+    input: Option<String>,
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--dump" => options.dump = true,
+            "--dissect" => options.dissect = true,
+            "--catalog" => {
+                options.catalog = Some(args.next().ok_or("Missing value for --catalog")?)
+            }
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("Unknown option: {}", flag))
+            }
+            path => {
+                if options.input.is_some() {
+                    return Err("Only one input file can be given".to_string());
+                }
+
+                options.input = Some(path.to_string());
+            }
+        }
+    }
+
+    Ok(Some(options))
+}
+
+pub fn run(options: &Options) -> Result<(), Error> {
+    if let Some(path) = &options.catalog {
+        install_catalog(path)?;
+    }
+
+    let input: Box<dyn BufRead> = match options.input.as_deref() {
+        None | Some("-") => Box::new(BufReader::new(io::stdin())),
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    if !options.json {
+        writeln!(out, "{:<6} MESSAGE", "LINE")?;
+    }
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let results = match parse_line(&line) {
+            Ok(data) => decode_line(&data),
+            Err(error) => vec![Err(error)],
+        };
+
+        for result in results {
+            if options.json {
+                let value = match result {
+                    Ok(message) => {
+                        let mut value = message_json(&message);
+                        value["line"] = json!(line_number);
+                        if options.dissect {
+                            value["dissection"] = json!(Message::dissect(&message.encode())?);
+                        }
+                        value
+                    }
+                    Err(error) => json!({ "line": line_number, "error": error.to_string() }),
+                };
+
+                writeln!(out, "{}", value)?;
+            } else {
+                match result {
+                    Ok(message) => {
+                        writeln!(out, "{:<6} {}", line_number, message)?;
+
+                        if options.dump {
+                            for row in message.hex_dump().lines() {
+                                writeln!(out, "{:<6} {}", "", row)?;
+                            }
+                        }
+
+                        if options.dissect {
+                            let dissection = Message::dissect(&message.encode())?;
+                            for row in dissection.to_string().lines() {
+                                writeln!(out, "{:<6} {}", "", row)?;
+                            }
+                        }
+                    }
+                    Err(error) => writeln!(out, "{:<6} Error: {}", line_number, error)?,
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes a line of hex digits, which may start with `0x` and separate bytes with whitespace,
+/// `:` or `-`.
+fn parse_line(line: &str) -> Result<Vec<u8>, Error> {
+    let digits: String = line
+        .trim()
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != ':' && *c != '-')
+        .collect();
+
+    hex::decode(&digits)
+}
+
+/// Strips any advertising data or company ID framing and decodes each message in the line.
+fn decode_line(data: &[u8]) -> Vec<Result<Message, Error>> {
+    let company_id = APPLE_COMPANY_ID.to_le_bytes();
+
+    // Full advertising data may lead with any structure, such as Flags, so look for the
+    // manufacturer data anywhere before falling back to the shorter forms
+    let data = if let Some(data) = manufacturer_data(data) {
+        data
+    } else if data.len() >= 2 && data[0..2] == company_id {
+        &data[2..]
+    } else {
+        data
+    };
+
+    let messages: Vec<_> = split_messages(data).map(Message::decode).collect();
+    if messages.is_empty() {
+        return vec![Err(Error::new(
+            ErrorKind::DecodeError,
+            "Line does not contain a complete message",
+        ))];
+    }
+
+    messages
+}
@@ -0,0 +1,58 @@
+use std::process::ExitCode;
+
+mod decode;
+mod output;
+mod replay;
+mod wireshark;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+
+    let result = match args.peek().map(String::as_str) {
+        Some("replay") => {
+            args.next();
+            match replay::parse_args(args) {
+                Ok(Some(options)) => replay::run(&options),
+                Ok(None) => return usage(replay::USAGE),
+                Err(error) => return usage_error(&error, replay::USAGE),
+            }
+        }
+        Some("wireshark") => {
+            args.next();
+            match wireshark::parse_args(args) {
+                Ok(Some(options)) => wireshark::run(&options),
+                Ok(None) => return usage(wireshark::USAGE),
+                Err(error) => return usage_error(&error, wireshark::USAGE),
+            }
+        }
+        command => {
+            if command == Some("decode") {
+                args.next();
+            }
+
+            match decode::parse_args(args) {
+                Ok(Some(options)) => decode::run(&options),
+                Ok(None) => return usage(decode::USAGE),
+                Err(error) => return usage_error(&error, decode::USAGE),
+            }
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("continuity: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage(usage: &str) -> ExitCode {
+    println!("{}", usage);
+    ExitCode::SUCCESS
+}
+
+fn usage_error(error: &str, usage: &str) -> ExitCode {
+    eprintln!("{}\n\n{}", error, usage);
+    ExitCode::FAILURE
+}
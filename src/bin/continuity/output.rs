@@ -0,0 +1,40 @@
+use apple_continuity::{
+    airplay::AirPlayReceiver,
+    airprint::{Printer, ResourceCatalog},
+    messages::Message,
+    proximity::ProximityDevice,
+    schema::SCHEMA_VERSION,
+    Error,
+};
+use serde_json::json;
+
+/// Adds the entries in a JSON catalog file to the installed `ResourceCatalog`, so printers
+/// are described and dissected with them.
+pub fn install_catalog(path: &str) -> Result<(), Error> {
+    let mut catalog = ResourceCatalog::current();
+    catalog.load(path)?;
+    catalog.install();
+    Ok(())
+}
+
+/// The message as JSON, along with its `ProximityDevice`, `Printer` or `AirPlayReceiver`
+/// interpretation.
+pub fn message_json(message: &Message) -> serde_json::Value {
+    let mut value = json!({ "schema_version": SCHEMA_VERSION, "message": message });
+    match message {
+        Message::ProximityPairing(message) => {
+            value["device"] = json!(ProximityDevice::from(message.clone()));
+        }
+        Message::Airprint(message) => {
+            let printer = Printer::from(message.clone());
+            value["uri"] = json!(printer.uri());
+            value["printer"] = json!(printer);
+        }
+        Message::AirplayTarget(message) => {
+            value["receiver"] = json!(AirPlayReceiver::from(message.clone()));
+        }
+        _ => {}
+    }
+
+    value
+}
@@ -0,0 +1,342 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use apple_continuity::{
+    advertisement::{Address, Advertisement},
+    capture::{self, CaptureFormat},
+    messages::{Message, MessageOpcode},
+    proximity::{DeviceShape, ProximityDevice, ProximityDeviceModel},
+    schema::SCHEMA_VERSION,
+    Error,
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::output::{install_catalog, message_json};
+
+pub const USAGE: &str = "Usage: continuity replay [OPTIONS] FILE...
+
+Replays btsnoop, pcap/pcapng or JSON lines captures, printing each decoded message
+followed by a summary per advertiser address.
+
+Options:
+    --format FORMAT   Capture format: pcap, btsnoop or jsonl. Detected when omitted
+    --opcode OPCODE   Only show messages with this opcode or type, e.g. 0x07 or
+                      proximity_pairing. Repeatable
+    --address ADDR    Only show messages from this address. Repeatable
+    --model MODEL     Only show Proximity Pairing messages from this model, e.g.
+                      airpods_pro or \"AirPods Pro\". Repeatable
+    --catalog CATALOG Resolve AirPrint resource paths and printer IDs with the entries
+                      in this JSON catalog as well as the built-in ones
+    --json            Print JSON lines instead of tables
+    --quiet           Only print the summary
+    -h, --help        Print this help";
+
+#[derive(Debug, Default)]
+pub struct Options {
+    format: Option<CaptureFormat>,
+    opcodes: Vec<u8>,
+    addresses: Vec<Address>,
+    models: Vec<ProximityDeviceModel>,
+    catalog: Option<String>,
+    json: bool,
+    quiet: bool,
+    inputs: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct Range {
+    min: u32,
+    max: u32,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct BatteryRange {
+    left: Option<Range>,
+    right: Option<Range>,
+    case: Option<Range>,
+    headphones: Option<Range>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceSummary {
+    address: Address,
+    first_seen: f64,
+    last_seen: f64,
+    messages: usize,
+    message_types: BTreeMap<String, usize>,
+    model: Option<ProximityDeviceModel>,
+    battery: BatteryRange,
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "--format" => {
+                options.format = Some(
+                    value("--format")?
+                        .parse()
+                        .map_err(|error: Error| error.to_string())?,
+                )
+            }
+            "--opcode" => {
+                let opcode = value("--opcode")?;
+                // Names and known opcodes, or any opcode in hex for custom decoders
+                options.opcodes.push(
+                    opcode
+                        .parse::<MessageOpcode>()
+                        .map(u8::from)
+                        .or_else(|_| u8::from_str_radix(opcode.trim_start_matches("0x"), 16))
+                        .map_err(|_| format!("Invalid opcode: {}", opcode))?,
+                );
+            }
+            "--address" => options.addresses.push(
+                value("--address")?
+                    .parse()
+                    .map_err(|error: Error| error.to_string())?,
+            ),
+            "--model" => options.models.push(
+                value("--model")?
+                    .parse()
+                    .map_err(|error: Error| error.to_string())?,
+            ),
+            "--catalog" => options.catalog = Some(value("--catalog")?),
+            "--json" => options.json = true,
+            "--quiet" => options.quiet = true,
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with('-') => return Err(format!("Unknown option: {}", flag)),
+            path => options.inputs.push(path.to_string()),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        return Err("No capture files given".to_string());
+    }
+
+    Ok(Some(options))
+}
+
+pub fn run(options: &Options) -> Result<(), Error> {
+    if let Some(path) = &options.catalog {
+        install_catalog(path)?;
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut summaries: BTreeMap<Address, DeviceSummary> = BTreeMap::new();
+
+    if !options.json && !options.quiet {
+        writeln!(
+            out,
+            "{:<18} {:<17} {:>5} MESSAGE",
+            "TIME", "ADDRESS", "RSSI"
+        )?;
+    }
+
+    for input in &options.inputs {
+        for advertisement in capture::open(input, options.format)? {
+            let advertisement = advertisement?;
+            if !options.addresses.is_empty() && !options.addresses.contains(&advertisement.address)
+            {
+                continue;
+            }
+
+            for message in advertisement.messages() {
+                if !matches_filters(options, &message) {
+                    continue;
+                }
+
+                summaries
+                    .entry(advertisement.address)
+                    .or_insert_with(|| DeviceSummary::new(&advertisement))
+                    .add(&advertisement, &message);
+
+                if options.quiet {
+                    continue;
+                }
+
+                if options.json {
+                    let mut value = message_json(&message);
+                    value["timestamp"] = json!(advertisement.timestamp.as_secs_f64());
+                    value["address"] = json!(advertisement.address);
+                    value["rssi"] = json!(advertisement.rssi);
+                    writeln!(out, "{}", value)?;
+                } else {
+                    writeln!(
+                        out,
+                        "{:<18} {:<17} {:>5} {}",
+                        format_timestamp(advertisement.timestamp),
+                        advertisement.address,
+                        advertisement
+                            .rssi
+                            .map(|rssi| rssi.to_string())
+                            .unwrap_or_else(|| "-".to_string()),
+                        message
+                    )?;
+                }
+            }
+        }
+    }
+
+    if options.json {
+        for summary in summaries.values() {
+            writeln!(
+                out,
+                "{}",
+                json!({ "schema_version": SCHEMA_VERSION, "summary": summary })
+            )?;
+        }
+    } else {
+        print_summaries(&mut out, &summaries)?;
+    }
+
+    Ok(())
+}
+
+fn matches_filters(options: &Options, message: &Message) -> bool {
+    if !options.opcodes.is_empty() && !options.opcodes.contains(&message.header().opcode) {
+        return false;
+    }
+
+    if options.models.is_empty() {
+        return true;
+    }
+
+    match message {
+        Message::ProximityPairing(message) => {
+            let model = ProximityDeviceModel::from(message.device_model);
+            options.models.contains(&model)
+        }
+        _ => false,
+    }
+}
+
+fn print_summaries<W: Write>(
+    out: &mut W,
+    summaries: &BTreeMap<Address, DeviceSummary>,
+) -> Result<(), Error> {
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{:<17} {:<18} {:<18} {:>8} {:<16} {:<11} {:<11} {:<11} {:<11} TYPES",
+        "ADDRESS",
+        "FIRST SEEN",
+        "LAST SEEN",
+        "MESSAGES",
+        "MODEL",
+        "LEFT",
+        "RIGHT",
+        "CASE",
+        "HEADPHONES"
+    )?;
+
+    for summary in summaries.values() {
+        let types: Vec<String> = summary
+            .message_types
+            .iter()
+            .map(|(name, count)| format!("{} x{}", name, count))
+            .collect();
+
+        writeln!(
+            out,
+            "{:<17} {:<18} {:<18} {:>8} {:<16} {:<11} {:<11} {:<11} {:<11} {}",
+            summary.address,
+            format!("{:.6}", summary.first_seen),
+            format!("{:.6}", summary.last_seen),
+            summary.messages,
+            summary
+                .model
+                .map(|model| model.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            format_range(summary.battery.left),
+            format_range(summary.battery.right),
+            format_range(summary.battery.case),
+            format_range(summary.battery.headphones),
+            types.join(", ")
+        )?;
+    }
+
+    Ok(())
+}
+
+fn format_timestamp(timestamp: Duration) -> String {
+    format!("{:.6}", timestamp.as_secs_f64())
+}
+
+fn format_range(range: Option<Range>) -> String {
+    match range {
+        Some(range) => format!("{}-{}%", range.min, range.max),
+        None => "-".to_string(),
+    }
+}
+
+impl Range {
+    fn add(range: Option<Range>, value: Option<u32>) -> Option<Range> {
+        let value = match value {
+            Some(value) => value,
+            None => return range,
+        };
+
+        Some(match range {
+            Some(range) => Range {
+                min: range.min.min(value),
+                max: range.max.max(value),
+            },
+            None => Range {
+                min: value,
+                max: value,
+            },
+        })
+    }
+}
+
+impl DeviceSummary {
+    fn new(advertisement: &Advertisement) -> DeviceSummary {
+        DeviceSummary {
+            address: advertisement.address,
+            first_seen: advertisement.timestamp.as_secs_f64(),
+            last_seen: advertisement.timestamp.as_secs_f64(),
+            messages: 0,
+            message_types: BTreeMap::new(),
+            model: None,
+            battery: BatteryRange::default(),
+        }
+    }
+
+    fn add(&mut self, advertisement: &Advertisement, message: &Message) {
+        let timestamp = advertisement.timestamp.as_secs_f64();
+        self.first_seen = self.first_seen.min(timestamp);
+        self.last_seen = self.last_seen.max(timestamp);
+        self.messages += 1;
+        *self
+            .message_types
+            .entry(message.name().to_string())
+            .or_default() += 1;
+
+        if let Message::ProximityPairing(message) = message {
+            let device = ProximityDevice::from(message.clone());
+            self.model = Some(device.model);
+            match device.shape {
+                DeviceShape::Earbuds(earbuds) => {
+                    self.battery.left = Range::add(self.battery.left, earbuds.battery.left);
+                    self.battery.right = Range::add(self.battery.right, earbuds.battery.right);
+                    self.battery.case = Range::add(self.battery.case, earbuds.battery.case);
+                }
+                DeviceShape::Headphones(headphones) => {
+                    self.battery.headphones =
+                        Range::add(self.battery.headphones, headphones.battery);
+                }
+            }
+        }
+    }
+}
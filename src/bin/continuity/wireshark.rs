@@ -0,0 +1,47 @@
+use std::fs;
+
+use apple_continuity::{wireshark::lua_dissector, Error};
+
+pub const USAGE: &str = "Usage: continuity wireshark [FILE]
+
+Writes a Wireshark Lua dissector for Apple Continuity manufacturer data, generated from the
+message layouts this version decodes. Prints to stdout when FILE is omitted.
+
+Options:
+    -h, --help  Print this help";
+
+#[derive(Debug, Default)]
+pub struct Options {
+    output: Option<String>,
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+
+    for arg in args {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("Unknown option: {}", flag))
+            }
+            path => {
+                if options.output.is_some() {
+                    return Err("Only one output file can be given".to_string());
+                }
+
+                options.output = Some(path.to_string());
+            }
+        }
+    }
+
+    Ok(Some(options))
+}
+
+pub fn run(options: &Options) -> Result<(), Error> {
+    match options.output.as_deref() {
+        None | Some("-") => print!("{}", lua_dissector()),
+        Some(path) => fs::write(path, lua_dissector())?,
+    }
+
+    Ok(())
+}
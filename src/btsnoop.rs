@@ -0,0 +1,270 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    advertisement::{Address, Advertisement, PduType},
+    Error, ErrorKind,
+};
+
+pub const BTSNOOP_MAGIC: &[u8; 8] = b"btsnoop\0";
+
+pub const DATALINK_HCI_UNENCAPSULATED: u32 = 1001;
+pub const DATALINK_HCI_UART: u32 = 1002;
+
+/// Microseconds between 0000-01-01 (the btsnoop epoch) and 1970-01-01.
+const BTSNOOP_EPOCH_OFFSET: u64 = 0x00DC_DDB3_0F2F_8000;
+
+const FLAG_RECEIVED: u32 = 0x01;
+const FLAG_COMMAND_OR_EVENT: u32 = 0x02;
+
+const HCI_EVENT_PACKET: u8 = 0x04;
+const HCI_LE_META_EVENT: u8 = 0x3E;
+const HCI_LE_ADVERTISING_REPORT: u8 = 0x02;
+const HCI_LE_EXTENDED_ADVERTISING_REPORT: u8 = 0x0D;
+
+const RSSI_NOT_AVAILABLE: i8 = 127;
+
+// HCI packets are at most a few kilobytes; anything larger is a corrupt length field
+const MAX_RECORD_LENGTH: usize = 256 * 1024;
+
+struct Record {
+    timestamp: Duration,
+    event: Option<Vec<u8>>,
+}
+
+/// Reads BLE advertisements out of btsnoop HCI logs, such as those produced by Android's
+/// Bluetooth HCI snoop log, using the LE Advertising Report and LE Extended Advertising
+/// Report events.
+pub struct BtsnoopReader<R: Read> {
+    reader: R,
+    datalink: u32,
+    pending: VecDeque<Advertisement>,
+    done: bool,
+}
+
+impl BtsnoopReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> BtsnoopReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0; 16];
+        reader.read_exact(&mut header)?;
+
+        if &header[0..8] != BTSNOOP_MAGIC {
+            return Err(Error::new(ErrorKind::CaptureError, "Not a btsnoop capture"));
+        }
+
+        let datalink = u32::from_be_bytes([header[12], header[13], header[14], header[15]]);
+        if datalink != DATALINK_HCI_UNENCAPSULATED && datalink != DATALINK_HCI_UART {
+            return Err(Error::new(
+                ErrorKind::CaptureError,
+                format!("Unsupported btsnoop datalink: {}", datalink).as_str(),
+            ));
+        }
+
+        Ok(BtsnoopReader {
+            reader,
+            datalink,
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    /// Reads the next record, keeping the HCI event it contains if it is one.
+    fn next_record(&mut self) -> Result<Option<Record>, Error> {
+        let mut header = [0; 24];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+
+        let included_length =
+            u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        let flags = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+        let timestamp = u64::from_be_bytes([
+            header[16], header[17], header[18], header[19], header[20], header[21], header[22],
+            header[23],
+        ]);
+
+        if included_length > MAX_RECORD_LENGTH {
+            return Err(Error::new(
+                ErrorKind::CaptureError,
+                format!("btsnoop record is too long: {} bytes", included_length).as_str(),
+            ));
+        }
+
+        let mut data = vec![0; included_length];
+        self.reader.read_exact(&mut data)?;
+
+        let timestamp = Duration::from_micros(timestamp.saturating_sub(BTSNOOP_EPOCH_OFFSET));
+        let event = match self.datalink {
+            DATALINK_HCI_UART if data.first() == Some(&HCI_EVENT_PACKET) => {
+                Some(data[1..].to_vec())
+            }
+            DATALINK_HCI_UNENCAPSULATED
+                if flags & (FLAG_RECEIVED | FLAG_COMMAND_OR_EVENT)
+                    == FLAG_RECEIVED | FLAG_COMMAND_OR_EVENT =>
+            {
+                Some(data)
+            }
+            _ => None,
+        };
+
+        Ok(Some(Record { timestamp, event }))
+    }
+}
+
+impl<R: Read> Iterator for BtsnoopReader<R> {
+    type Item = Result<Advertisement, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            match self.next_record() {
+                Ok(Some(Record {
+                    timestamp,
+                    event: Some(event),
+                })) => self.pending.extend(decode_event(&event, timestamp)),
+                Ok(Some(_)) => {}
+                Ok(None) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Decodes the advertisements in an HCI event, starting at the event code. Anything other than
+/// an LE Advertising Report or LE Extended Advertising Report yields nothing.
+pub fn decode_event(event: &[u8], timestamp: Duration) -> Vec<Advertisement> {
+    if event.len() < 4 || event[0] != HCI_LE_META_EVENT {
+        return Vec::new();
+    }
+
+    let parameters = &event[2..event.len().min(2 + event[1] as usize)];
+    match parameters.first().copied() {
+        Some(HCI_LE_ADVERTISING_REPORT) => decode_advertising_report(&parameters[1..], timestamp),
+        Some(HCI_LE_EXTENDED_ADVERTISING_REPORT) => {
+            decode_extended_advertising_report(&parameters[1..], timestamp)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The legacy report stores each field as an array across all reports.
+fn decode_advertising_report(data: &[u8], timestamp: Duration) -> Vec<Advertisement> {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return Vec::new(),
+    };
+
+    let event_types = 1;
+    let address_types = event_types + count;
+    let addresses = address_types + count;
+    let data_lengths = addresses + count * 6;
+    let report_data = data_lengths + count;
+    if data.len() < report_data {
+        return Vec::new();
+    }
+
+    let mut advertisements = Vec::with_capacity(count);
+    let mut offset = report_data;
+    for i in 0..count {
+        let length = data[data_lengths + i] as usize;
+        if data.len() < offset + length {
+            break;
+        }
+
+        advertisements.push(Advertisement {
+            timestamp,
+            address: Address::from_le_bytes(&data[addresses + i * 6..addresses + i * 6 + 6]),
+            random_address: data[address_types + i] & 0x01 != 0,
+            pdu_type: match data[event_types + i] {
+                0x00 => PduType::AdvInd,
+                0x01 => PduType::AdvDirectInd,
+                0x02 => PduType::AdvScanInd,
+                0x03 => PduType::AdvNonconnInd,
+                0x04 => PduType::ScanRsp,
+                _ => PduType::Unknown,
+            },
+            rssi: None,
+            channel: None,
+            data: data[offset..offset + length].to_vec(),
+        });
+        offset += length;
+    }
+
+    let rssi_offset = offset;
+    for (i, advertisement) in advertisements.iter_mut().enumerate() {
+        advertisement.rssi = data
+            .get(rssi_offset + i)
+            .map(|rssi| *rssi as i8)
+            .filter(|rssi| *rssi != RSSI_NOT_AVAILABLE);
+    }
+
+    advertisements
+}
+
+fn decode_extended_advertising_report(data: &[u8], timestamp: Duration) -> Vec<Advertisement> {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return Vec::new(),
+    };
+
+    let mut advertisements = Vec::with_capacity(count);
+    let mut offset = 1;
+    for _ in 0..count {
+        if data.len() < offset + 24 {
+            break;
+        }
+
+        let report = &data[offset..];
+        let length = report[23] as usize;
+        if report.len() < 24 + length {
+            break;
+        }
+
+        let event_type = u16::from_le_bytes([report[0], report[1]]);
+        let rssi = report[13] as i8;
+
+        advertisements.push(Advertisement {
+            timestamp,
+            address: Address::from_le_bytes(&report[3..9]),
+            random_address: report[2] & 0x01 != 0,
+            pdu_type: if event_type & 0x08 != 0 {
+                PduType::ScanRsp
+            } else if event_type & 0x04 != 0 {
+                PduType::AdvDirectInd
+            } else if event_type & 0x01 != 0 {
+                PduType::AdvInd
+            } else if event_type & 0x02 != 0 {
+                PduType::AdvScanInd
+            } else {
+                PduType::AdvNonconnInd
+            },
+            rssi: if rssi == RSSI_NOT_AVAILABLE {
+                None
+            } else {
+                Some(rssi)
+            },
+            channel: None,
+            data: report[24..24 + length].to_vec(),
+        });
+
+        offset += 24 + length;
+    }
+
+    advertisements
+}
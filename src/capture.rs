@@ -0,0 +1,91 @@
+use std::{fmt::Display, fs::File, io::Read, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    advertisement::Advertisement, btsnoop::BtsnoopReader, jsonl::JsonlReader, pcap::PcapReader,
+    Error, ErrorKind,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureFormat {
+    Pcap,
+    Btsnoop,
+    Jsonl,
+}
+
+pub type Advertisements = Box<dyn Iterator<Item = Result<Advertisement, Error>>>;
+
+impl CaptureFormat {
+    /// Detects the format from the first bytes of a capture. Anything that isn't pcap, pcapng
+    /// or btsnoop is assumed to be JSON lines.
+    pub fn detect(header: &[u8]) -> CaptureFormat {
+        if header.starts_with(crate::btsnoop::BTSNOOP_MAGIC) {
+            return CaptureFormat::Btsnoop;
+        }
+
+        match header.get(0..4) {
+            Some([0x0A, 0x0D, 0x0D, 0x0A])
+            | Some([0xD4, 0xC3, 0xB2, 0xA1])
+            | Some([0xA1, 0xB2, 0xC3, 0xD4])
+            | Some([0x4D, 0x3C, 0xB2, 0xA1])
+            | Some([0xA1, 0xB2, 0x3C, 0x4D]) => CaptureFormat::Pcap,
+            _ => CaptureFormat::Jsonl,
+        }
+    }
+}
+
+impl FromStr for CaptureFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pcap" | "pcapng" => Ok(CaptureFormat::Pcap),
+            "btsnoop" => Ok(CaptureFormat::Btsnoop),
+            "jsonl" | "json" => Ok(CaptureFormat::Jsonl),
+            _ => Err(Error::new(
+                ErrorKind::CaptureError,
+                format!("Unknown capture format: {}", s).as_str(),
+            )),
+        }
+    }
+}
+
+impl Display for CaptureFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CaptureFormat::Pcap => "pcap",
+                CaptureFormat::Btsnoop => "btsnoop",
+                CaptureFormat::Jsonl => "jsonl",
+            }
+        )
+    }
+}
+
+/// Opens a capture file, detecting its format unless one is given.
+pub fn open<P: AsRef<Path>>(
+    path: P,
+    format: Option<CaptureFormat>,
+) -> Result<Advertisements, Error> {
+    let format = match format {
+        Some(format) => format,
+        None => {
+            let mut header = Vec::with_capacity(8);
+            File::open(path.as_ref())?
+                .take(8)
+                .read_to_end(&mut header)?;
+            CaptureFormat::detect(&header)
+        }
+    };
+
+    Ok(match format {
+        CaptureFormat::Pcap => Box::new(PcapReader::open(path)?),
+        CaptureFormat::Btsnoop => Box::new(BtsnoopReader::open(path)?),
+        CaptureFormat::Jsonl => Box::new(JsonlReader::open(path)?),
+    })
+}
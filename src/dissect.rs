@@ -0,0 +1,265 @@
+use std::{
+    fmt::Display,
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::Range,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    airplay::AirPlayFlags,
+    airprint::{AddressType, ResourceCatalog, ResourceType, SecurityType},
+    layout::FieldLayout,
+    messages::Message,
+    proximity::{PairingMode, Pod, ProximityDeviceModel, Status},
+};
+
+/// A node in a message dissection. Fields cover a byte range of the message; bitfields cover a
+/// single byte and have a `mask`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Field {
+    pub name: String,
+    pub range: Range<usize>,
+    pub mask: Option<u8>,
+    /// The bytes the field covers, before masking.
+    #[serde(with = "crate::hex")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub raw: Vec<u8>,
+    /// The big-endian value of the field, shifted down for bitfields. `None` for fields over
+    /// eight bytes.
+    pub value: Option<u64>,
+    pub interpreted: Option<String>,
+    pub children: Vec<Field>,
+}
+
+impl Field {
+    pub fn new(name: &str, data: &[u8], range: Range<usize>) -> Field {
+        let raw = data[range.clone()].to_vec();
+        let value = if raw.len() <= 8 {
+            Some(
+                raw.iter()
+                    .fold(0, |value, byte| (value << 8) | *byte as u64),
+            )
+        } else {
+            None
+        };
+
+        Field {
+            name: name.to_string(),
+            range,
+            mask: None,
+            raw,
+            value,
+            interpreted: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// The bits of the byte at `offset` selected by `mask`.
+    pub fn bits(name: &str, data: &[u8], offset: usize, mask: u8) -> Field {
+        let byte = data[offset];
+        Field {
+            mask: Some(mask),
+            value: Some(((byte & mask) >> mask.trailing_zeros()) as u64),
+            ..Field::new(name, data, offset..offset + 1)
+        }
+    }
+
+    pub fn interpreted<T: Display>(mut self, interpreted: T) -> Field {
+        self.interpreted = Some(interpreted.to_string());
+        self
+    }
+
+    pub fn children(mut self, children: Vec<Field>) -> Field {
+        self.children = children;
+        self
+    }
+
+    /// The field's value, or 0 when it's too wide to have one.
+    fn number(&self) -> u64 {
+        self.value.unwrap_or_default()
+    }
+
+    fn write_tree(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}", "", indent = depth * 4)?;
+
+        match self.mask {
+            // Wireshark style, such as `.... .11. = name: value`
+            Some(mask) => {
+                let bits: String = (0..8)
+                    .rev()
+                    .map(|bit| {
+                        if mask & (1 << bit) == 0 {
+                            '.'
+                        } else if self.raw[0] & (1 << bit) == 0 {
+                            '0'
+                        } else {
+                            '1'
+                        }
+                    })
+                    .collect();
+                write!(f, "{} {} = {}", &bits[..4], &bits[4..], self.name)?;
+            }
+            None => write!(
+                f,
+                "[{:02X}..{:02X}] {}: {}",
+                self.range.start,
+                self.range.end,
+                self.name,
+                crate::hex::encode(&self.raw)
+            )?,
+        }
+
+        match (&self.interpreted, self.mask, self.value) {
+            (Some(interpreted), _, _) => writeln!(f, " ({})", interpreted)?,
+            (None, Some(_), Some(value)) => writeln!(f, " ({})", value)?,
+            _ => writeln!(f)?,
+        }
+
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Field {
+    /// The field and its children as an indented tree, one field per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+fn level(nibble: u64) -> String {
+    match nibble {
+        0x00..=0x0A => format!("{}%", nibble * 10),
+        0x0F => "not connected".to_string(),
+        _ => "undefined".to_string(),
+    }
+}
+
+fn flag(set: u64) -> &'static str {
+    if set != 0 {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Dissects the field laid out by `layout` of `message`, which covers `range` of `data`.
+pub(crate) fn field(
+    message: &Message,
+    layout: &FieldLayout,
+    data: &[u8],
+    range: Range<usize>,
+) -> Field {
+    let offset = range.start;
+    let children = if range.is_empty() {
+        Vec::new()
+    } else {
+        layout
+            .bits
+            .iter()
+            .map(|bits| interpret_bits(Field::bits(bits.name, data, offset, bits.mask)))
+            .collect()
+    };
+
+    interpret(message, Field::new(layout.name, data, range)).children(children)
+}
+
+fn interpret(message: &Message, field: Field) -> Field {
+    let number = field.number();
+
+    match (message, field.name.as_str()) {
+        (_, "opcode") => field.interpreted(message.name()),
+        (_, "length") => field.interpreted(format!("{} bytes", number)),
+        (Message::ProximityPairing(_), "prefix") => {
+            field.interpreted(PairingMode::from(number as u8))
+        }
+        (Message::ProximityPairing(_), "device_model") => {
+            field.interpreted(ProximityDeviceModel::from(number as u16))
+        }
+        (Message::ProximityPairing(message), "device_color") => {
+            let model = ProximityDeviceModel::from(message.device_model);
+            let name = model.color_name(number as u8).unwrap_or("unknown color");
+            field.interpreted(name)
+        }
+        (Message::Airprint(message), "rp_index_value") => {
+            match ResourceType::from(message.connection_info) {
+                ResourceType::ResourcePath => {
+                    let path = ResourceCatalog::with_installed(|catalog| {
+                        catalog
+                            .resource_path(number as u16)
+                            .unwrap_or("unknown path")
+                            .to_string()
+                    });
+                    field.interpreted(path)
+                }
+                ResourceType::PrinterId => {
+                    let vendor = ResourceCatalog::with_installed(|catalog| {
+                        catalog
+                            .printer(number as u16)
+                            .map(|entry| entry.vendor.clone())
+                    });
+                    match vendor {
+                        Some(vendor) => {
+                            field.interpreted(format!("printer ID {} ({})", number, vendor))
+                        }
+                        None => field.interpreted(format!("printer ID {}", number)),
+                    }
+                }
+                ResourceType::Unknown => field,
+            }
+        }
+        (Message::Airprint(message), "address") => {
+            match AddressType::from(message.connection_info) {
+                AddressType::IPv4 => field.interpreted(Ipv4Addr::new(
+                    message.address[0],
+                    message.address[1],
+                    message.address[2],
+                    message.address[3],
+                )),
+                AddressType::IPv6 => field.interpreted(Ipv6Addr::from(message.address)),
+                AddressType::Unknown => field,
+            }
+        }
+        (Message::Airprint(_), "measured_power") => {
+            field.interpreted(format!("{} dBm", number as u8 as i8))
+        }
+        (Message::AirplayTarget(_), "flags") => field.interpreted(AirPlayFlags::from(number as u8)),
+        (Message::AirplayTarget(_), "ip4_address") => {
+            field.interpreted(Ipv4Addr::from(number as u32))
+        }
+        (_, "port") | (_, "lid_open_count") | (_, "seed") | (_, "iv") => field.interpreted(number),
+        _ => field,
+    }
+}
+
+fn interpret_bits(field: Field) -> Field {
+    let number = field.number();
+    let byte = field.raw[0];
+
+    match field.name.as_str() {
+        "primary_pod" => field.interpreted(if number != 0 { Pod::Left } else { Pod::Right }),
+        "secondary_pod_status" | "primary_pod_status" => {
+            field.interpreted(Status::from(number as u8))
+        }
+        "secondary_pod_battery" | "primary_pod_battery" | "case_battery" => {
+            field.interpreted(level(number))
+        }
+        "case_open"
+        | "case_charging"
+        | "secondary_pod_charging"
+        | "primary_pod_charging"
+        | "access_control"
+        | "pin_required"
+        | "password_required"
+        | "screen_mirroring" => field.interpreted(flag(number)),
+        "address_type" => field.interpreted(AddressType::from(byte)),
+        "resource_type" => field.interpreted(ResourceType::from(byte)),
+        "security_type" => field.interpreted(SecurityType::from(byte)),
+        _ => field,
+    }
+}
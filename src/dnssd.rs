@@ -0,0 +1,109 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::airprint::{Printer, SecurityType};
+
+pub const IPP_SERVICE: &str = "_ipp._tcp";
+pub const IPPS_SERVICE: &str = "_ipps._tcp";
+pub const LOCAL_DOMAIN: &str = "local.";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TxtRecord {
+    pub key: String,
+    pub value: String,
+}
+
+/// The DNS-SD records advertising a printer on the local network: the PTR from the service
+/// type to the instance, its SRV and TXT records, and the A or AAAA record for the SRV target.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ServiceRecords {
+    pub instance: String,
+    /// `_ipp._tcp`, or `_ipps._tcp` for printers requiring TLS.
+    pub service_type: String,
+    pub domain: String,
+    pub srv: SrvRecord,
+    pub txt: Vec<TxtRecord>,
+    pub address: IpAddr,
+}
+
+impl TxtRecord {
+    pub fn new(key: &str, value: &str) -> TxtRecord {
+        TxtRecord {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl ServiceRecords {
+    /// The records republishing `printer` under `instance`, or `None` when the printer has no
+    /// address or IPP path.
+    pub fn from_printer(printer: &Printer, instance: &str) -> Option<ServiceRecords> {
+        let address = printer.ip_addr()?;
+
+        let service_type = match printer.security_type {
+            SecurityType::TLS => IPPS_SERVICE,
+            _ => IPP_SERVICE,
+        };
+
+        // The address is all that's known about the host, so derive a unique name from it
+        let target = format!(
+            "airprint-{}.{}",
+            address.to_string().replace(['.', ':'], "-"),
+            LOCAL_DOMAIN
+        );
+
+        let mut txt = vec![
+            TxtRecord::new("txtvers", "1"),
+            TxtRecord::new("qtotal", "1"),
+        ];
+
+        // The rp key holds the resource path without its leading slash
+        let resource_path = printer.ipp_path()?;
+        txt.push(TxtRecord::new("rp", resource_path.trim_start_matches('/')));
+
+        if printer.security_type == SecurityType::TLS {
+            txt.push(TxtRecord::new("TLS", "1.2"));
+        }
+
+        if let Some(printer_id) = printer.printer_id {
+            txt.push(TxtRecord::new("printer-id", &printer_id.to_string()));
+        }
+
+        Some(ServiceRecords {
+            instance: instance.to_string(),
+            service_type: service_type.to_string(),
+            domain: LOCAL_DOMAIN.to_string(),
+            srv: SrvRecord {
+                priority: 0,
+                weight: 0,
+                port: printer.port,
+                target,
+            },
+            txt,
+            address,
+        })
+    }
+
+    /// The service type the PTR record is published under, such as `_ipp._tcp.local.`.
+    pub fn service_name(&self) -> String {
+        format!("{}.{}", self.service_type, self.domain)
+    }
+
+    /// The instance's full name, such as `Office Printer._ipp._tcp.local.`.
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.instance, self.service_name())
+    }
+}
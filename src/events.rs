@@ -0,0 +1,298 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proximity::{DeviceShape, Earbuds, Headphones, ProximityDevice, Status};
+
+// A step of the 8-bit lid open counter further than this is a step backwards that wrapped, as
+// when the case resets its counter
+const MAX_LID_STEP: u8 = 0x7F;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    Left,
+    Right,
+    Case,
+    Headphones,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Rising,
+    Falling,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ProximityEvent {
+    CaseOpened,
+    CaseClosed,
+    InsertedInEar(Component),
+    RemovedFromEar(Component),
+    PlacedInCase(Component),
+    RemovedFromCase(Component),
+    PutOnHead,
+    RemovedFromHead,
+    ChargingStarted(Component),
+    ChargingStopped(Component),
+    BatteryThresholdCrossed {
+        component: Component,
+        threshold: u32,
+        direction: Direction,
+    },
+    LidOpenCountIncremented {
+        count: u8,
+    },
+}
+
+/// Turns successive `ProximityDevice` values for one device into semantic events.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProximityEventStream {
+    previous: Option<ProximityDevice>,
+    battery_thresholds: Vec<u32>,
+}
+
+impl Default for ProximityEventStream {
+    fn default() -> Self {
+        ProximityEventStream::new(vec![10, 20, 50])
+    }
+}
+
+impl ProximityEventStream {
+    /// Creates a stream reporting battery levels crossing each of `battery_thresholds`.
+    pub fn new(battery_thresholds: Vec<u32>) -> ProximityEventStream {
+        ProximityEventStream {
+            previous: None,
+            battery_thresholds,
+        }
+    }
+
+    /// Compares `device` to the previous value pushed, returning nothing for the first.
+    pub fn push(&mut self, device: ProximityDevice) -> Vec<ProximityEvent> {
+        let events = match &self.previous {
+            Some(previous) => diff(previous, &device, &self.battery_thresholds),
+            None => Vec::new(),
+        };
+
+        self.previous = Some(device);
+        events
+    }
+
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+}
+
+impl Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Component::Left => "left pod",
+                Component::Right => "right pod",
+                Component::Case => "case",
+                Component::Headphones => "headphones",
+            }
+        )
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Direction::Rising => "rose above",
+                Direction::Falling => "fell below",
+            }
+        )
+    }
+}
+
+impl Display for ProximityEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProximityEvent::CaseOpened => write!(f, "case opened"),
+            ProximityEvent::CaseClosed => write!(f, "case closed"),
+            ProximityEvent::InsertedInEar(component) => write!(f, "{} inserted in ear", component),
+            ProximityEvent::RemovedFromEar(component) => {
+                write!(f, "{} removed from ear", component)
+            }
+            ProximityEvent::PlacedInCase(component) => write!(f, "{} placed in case", component),
+            ProximityEvent::RemovedFromCase(component) => {
+                write!(f, "{} removed from case", component)
+            }
+            ProximityEvent::PutOnHead => write!(f, "headphones put on"),
+            ProximityEvent::RemovedFromHead => write!(f, "headphones taken off"),
+            ProximityEvent::ChargingStarted(component) => {
+                write!(f, "{} started charging", component)
+            }
+            ProximityEvent::ChargingStopped(component) => {
+                write!(f, "{} stopped charging", component)
+            }
+            ProximityEvent::BatteryThresholdCrossed {
+                component,
+                threshold,
+                direction,
+            } => write!(f, "{} battery {} {}%", component, direction, threshold),
+            ProximityEvent::LidOpenCountIncremented { count } => {
+                write!(f, "lid opened (count {})", count)
+            }
+        }
+    }
+}
+
+/// The events between two successive values of the same device. Nothing but the lid open
+/// count is compared when the two values have different shapes.
+pub fn diff(
+    previous: &ProximityDevice,
+    current: &ProximityDevice,
+    battery_thresholds: &[u32],
+) -> Vec<ProximityEvent> {
+    let mut events = Vec::new();
+
+    let lid_step = current.lid_open_count.wrapping_sub(previous.lid_open_count);
+    if (1..=MAX_LID_STEP).contains(&lid_step) {
+        events.push(ProximityEvent::LidOpenCountIncremented {
+            count: current.lid_open_count,
+        });
+    }
+
+    let (charging, battery) = match (&previous.shape, &current.shape) {
+        (DeviceShape::Earbuds(previous), DeviceShape::Earbuds(current)) => {
+            diff_earbuds(previous, current, &mut events)
+        }
+        (DeviceShape::Headphones(previous), DeviceShape::Headphones(current)) => {
+            diff_headphones(previous, current, &mut events)
+        }
+        _ => return events,
+    };
+
+    for (component, previous, current) in charging {
+        if previous != current {
+            events.push(if current {
+                ProximityEvent::ChargingStarted(component)
+            } else {
+                ProximityEvent::ChargingStopped(component)
+            });
+        }
+    }
+
+    for (component, previous, current) in battery {
+        let (previous, current) = match (previous, current) {
+            (Some(previous), Some(current)) => (previous, current),
+            _ => continue,
+        };
+
+        for threshold in battery_thresholds {
+            if previous >= *threshold && current < *threshold {
+                events.push(ProximityEvent::BatteryThresholdCrossed {
+                    component,
+                    threshold: *threshold,
+                    direction: Direction::Falling,
+                });
+            } else if previous < *threshold && current >= *threshold {
+                events.push(ProximityEvent::BatteryThresholdCrossed {
+                    component,
+                    threshold: *threshold,
+                    direction: Direction::Rising,
+                });
+            }
+        }
+    }
+
+    events
+}
+
+type Changes<T> = Vec<(Component, T, T)>;
+
+/// Pushes the placement events and returns the charging and battery values to compare.
+fn diff_earbuds(
+    previous: &Earbuds,
+    current: &Earbuds,
+    events: &mut Vec<ProximityEvent>,
+) -> (Changes<bool>, Changes<Option<u32>>) {
+    if previous.state.case_open != current.state.case_open {
+        events.push(if current.state.case_open {
+            ProximityEvent::CaseOpened
+        } else {
+            ProximityEvent::CaseClosed
+        });
+    }
+
+    for (component, previous, current) in [
+        (Component::Left, previous.state.left, current.state.left),
+        (Component::Right, previous.state.right, current.state.right),
+    ] {
+        if previous == current {
+            continue;
+        }
+
+        match previous {
+            Status::InEar => events.push(ProximityEvent::RemovedFromEar(component)),
+            Status::InCase => events.push(ProximityEvent::RemovedFromCase(component)),
+            _ => {}
+        }
+
+        match current {
+            Status::InEar => events.push(ProximityEvent::InsertedInEar(component)),
+            Status::InCase => events.push(ProximityEvent::PlacedInCase(component)),
+            _ => {}
+        }
+    }
+
+    (
+        vec![
+            (
+                Component::Left,
+                previous.charging_state.left,
+                current.charging_state.left,
+            ),
+            (
+                Component::Right,
+                previous.charging_state.right,
+                current.charging_state.right,
+            ),
+            (
+                Component::Case,
+                previous.charging_state.case,
+                current.charging_state.case,
+            ),
+        ],
+        vec![
+            (Component::Left, previous.battery.left, current.battery.left),
+            (
+                Component::Right,
+                previous.battery.right,
+                current.battery.right,
+            ),
+            (Component::Case, previous.battery.case, current.battery.case),
+        ],
+    )
+}
+
+fn diff_headphones(
+    previous: &Headphones,
+    current: &Headphones,
+    events: &mut Vec<ProximityEvent>,
+) -> (Changes<bool>, Changes<Option<u32>>) {
+    if previous.on_head != current.on_head {
+        events.push(if current.on_head {
+            ProximityEvent::PutOnHead
+        } else {
+            ProximityEvent::RemovedFromHead
+        });
+    }
+
+    (
+        vec![(Component::Headphones, previous.charging, current.charging)],
+        vec![(Component::Headphones, previous.battery, current.battery)],
+    )
+}
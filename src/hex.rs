@@ -0,0 +1,52 @@
+//! Lowercase hex strings for raw byte fields. Used with `#[serde(with = "crate::hex")]`, which
+//! serializes bytes as a hex string and accepts either a hex string or an array of bytes.
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+use crate::{Error, ErrorKind};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Hex(String),
+    Bytes(Vec<u8>),
+}
+
+pub fn encode(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn decode(digits: &str) -> Result<Vec<u8>, Error> {
+    let digits = digits.trim();
+    if !digits.len().is_multiple_of(2) {
+        return Err(Error::new(
+            ErrorKind::DecodeError,
+            "Hex string has an odd number of digits",
+        ));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2).unwrap_or("-"), 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| Error::new(ErrorKind::DecodeError, "Not a hex string"))
+}
+
+pub fn serialize<T: AsRef<[u8]>, S: Serializer>(
+    data: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(data.as_ref()))
+}
+
+pub fn deserialize<'de, T: TryFrom<Vec<u8>>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    let data = match Raw::deserialize(deserializer)? {
+        Raw::Hex(digits) => decode(&digits).map_err(D::Error::custom)?,
+        Raw::Bytes(data) => data,
+    };
+
+    let length = data.len();
+    T::try_from(data).map_err(|_| D::Error::custom(format!("Unexpected length {}", length)))
+}
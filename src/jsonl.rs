@@ -0,0 +1,115 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    advertisement::{Address, Advertisement, PduType},
+    hex, Error, ErrorKind,
+};
+
+/// One advertisement per line, as written by scanners that log to JSON. `timestamp` is in
+/// seconds since the Unix epoch and `data` is the advertising data as a hex string.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct JsonlRecord {
+    #[serde(default)]
+    pub timestamp: f64,
+    pub address: Address,
+    #[serde(default)]
+    pub random_address: bool,
+    #[serde(default)]
+    pub rssi: Option<i8>,
+    #[serde(default)]
+    pub channel: Option<u8>,
+    pub data: String,
+}
+
+pub struct JsonlReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    line: usize,
+}
+
+impl JsonlReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> JsonlReader<R> {
+    pub fn new(reader: R) -> Self {
+        JsonlReader {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonlReader<R> {
+    type Item = Result<Advertisement, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error.into())),
+            };
+            self.line += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(
+                serde_json::from_str::<JsonlRecord>(&line)
+                    .map_err(|error| {
+                        Error::new(
+                            ErrorKind::CaptureError,
+                            format!("Line {}: {}", self.line, error).as_str(),
+                        )
+                    })
+                    .and_then(Advertisement::try_from),
+            );
+        }
+    }
+}
+
+impl TryFrom<JsonlRecord> for Advertisement {
+    type Error = crate::Error;
+
+    fn try_from(value: JsonlRecord) -> Result<Self, Self::Error> {
+        let data = hex::decode(&value.data).map_err(|_| {
+            Error::new(
+                ErrorKind::CaptureError,
+                "Advertising data is not a hex string",
+            )
+        })?;
+
+        Ok(Advertisement {
+            timestamp: Duration::try_from_secs_f64(value.timestamp).unwrap_or_default(),
+            address: value.address,
+            random_address: value.random_address,
+            pdu_type: PduType::AdvInd,
+            rssi: value.rssi,
+            channel: value.channel,
+            data,
+        })
+    }
+}
+
+impl From<&Advertisement> for JsonlRecord {
+    fn from(value: &Advertisement) -> Self {
+        JsonlRecord {
+            timestamp: value.timestamp.as_secs_f64(),
+            address: value.address,
+            random_address: value.random_address,
+            rssi: value.rssi,
+            channel: value.channel,
+            data: hex::encode(&value.data),
+        }
+    }
+}
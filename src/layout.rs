@@ -0,0 +1,500 @@
+//! The wire layout of each message type: its fields in the order they're encoded, and the
+//! bitfields packed into single-byte fields. Message types are declared with `message!`, which
+//! generates the struct, its `decode` and `encode`, its layout and a field list in its docs, so
+//! dissection, hex dumps and the generated Wireshark dissector agree on where every field lives.
+
+use crate::{
+    messages::{
+        AirPrintMessage, AirplayTargetMessage, HandoffMessage, MessageHeader, NearbyInfoMessage,
+        ProximityPairMessage,
+    },
+    Error, ErrorKind,
+};
+
+/// Bits of a single-byte field selected by `mask`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitField {
+    pub name: &'static str,
+    pub mask: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldLayout {
+    pub name: &'static str,
+    /// Size in bytes, or `None` for a field taking up the rest of the message.
+    pub size: Option<usize>,
+    pub bits: &'static [BitField],
+    /// The field's doc comment, possibly empty.
+    pub doc: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessageLayout {
+    pub opcode: u8,
+    pub name: &'static str,
+    /// The fields following the opcode and length.
+    pub fields: &'static [FieldLayout],
+}
+
+/// The opcode and length every message starts with.
+pub const HEADER: &[FieldLayout] = &[
+    FieldLayout {
+        name: "opcode",
+        size: Some(1),
+        bits: &[],
+        doc: "The message type.",
+    },
+    FieldLayout {
+        name: "length",
+        size: Some(1),
+        bits: &[],
+        doc: "The number of bytes following the length.",
+    },
+];
+
+/// Every message type the crate decodes.
+pub const LAYOUTS: &[MessageLayout] = &[
+    AirPrintMessage::LAYOUT,
+    ProximityPairMessage::LAYOUT,
+    AirplayTargetMessage::LAYOUT,
+    HandoffMessage::LAYOUT,
+    NearbyInfoMessage::LAYOUT,
+];
+
+pub fn layout(opcode: u8) -> Option<&'static MessageLayout> {
+    LAYOUTS.iter().find(|layout| layout.opcode == opcode)
+}
+
+impl MessageLayout {
+    /// A lowercase identifier for the message, such as `proximity_pairing`.
+    pub fn key(&self) -> String {
+        self.name.to_lowercase().replace(' ', "_")
+    }
+
+    /// The smallest length byte a message can have to hold every fixed size field.
+    pub fn min_length(&self) -> usize {
+        self.fields.iter().filter_map(|field| field.size).sum()
+    }
+
+    /// Each field, header included, with its size in a message `length` bytes long (opcode
+    /// and length included). Fields sized by the rest of the message get whatever the fixed
+    /// size fields leave, possibly nothing.
+    pub fn sizes(&self, length: usize) -> Vec<(&'static FieldLayout, usize)> {
+        let fixed = self.min_length() + HEADER.len();
+
+        HEADER
+            .iter()
+            .chain(self.fields)
+            .map(|field| (field, field.size.unwrap_or(length.saturating_sub(fixed))))
+            .collect()
+    }
+}
+
+/// A type message fields can be declared as: big-endian integers, and raw bytes.
+pub trait Wire: Sized {
+    /// Size in bytes, or `None` for a field taking up the rest of the message.
+    const SIZE: Option<usize>;
+
+    /// Reads the field from exactly the bytes it covers.
+    fn read(data: &[u8]) -> Self;
+
+    /// Appends the field to `data`, taking up `size` bytes.
+    fn write(&self, size: usize, data: &mut Vec<u8>);
+}
+
+macro_rules! wire_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Wire for $ty {
+                const SIZE: Option<usize> = Some(std::mem::size_of::<$ty>());
+
+                fn read(data: &[u8]) -> Self {
+                    data.iter()
+                        .fold(0u64, |value, byte| (value << 8) | *byte as u64) as $ty
+                }
+
+                fn write(&self, size: usize, data: &mut Vec<u8>) {
+                    let bytes = self.to_be_bytes();
+                    data.extend_from_slice(&bytes[bytes.len().saturating_sub(size)..]);
+                }
+            }
+        )*
+    };
+}
+
+wire_integer!(u8, u16, u32);
+
+impl<const N: usize> Wire for [u8; N] {
+    const SIZE: Option<usize> = Some(N);
+
+    fn read(data: &[u8]) -> Self {
+        let mut array = [0; N];
+        array.copy_from_slice(data);
+        array
+    }
+
+    fn write(&self, _size: usize, data: &mut Vec<u8>) {
+        data.extend_from_slice(self);
+    }
+}
+
+impl Wire for Vec<u8> {
+    const SIZE: Option<usize> = None;
+
+    fn read(data: &[u8]) -> Self {
+        data.to_vec()
+    }
+
+    fn write(&self, _size: usize, data: &mut Vec<u8>) {
+        data.extend_from_slice(self);
+    }
+}
+
+/// Reads the fields of an encoded message in layout order.
+pub(crate) struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+    sizes: std::vec::IntoIter<usize>,
+}
+
+impl<'a> Cursor<'a> {
+    /// Checks the opcode and lengths of `data` against `layout`, returning the header and a
+    /// cursor at the first field after it.
+    pub(crate) fn new(
+        data: &'a [u8],
+        layout: &MessageLayout,
+    ) -> Result<(MessageHeader, Cursor<'a>), Error> {
+        if data.len() < 2 {
+            return Err(Error::new(
+                ErrorKind::DecodeError,
+                "Length mismatch. Cannot read opcode + length",
+            ));
+        }
+
+        let opcode = data[0];
+        if opcode != layout.opcode {
+            return Err(Error::new(
+                ErrorKind::DecodeError,
+                &format!(
+                    "Tried to decode {} message with invalid opcode. Expected {:#04X}",
+                    layout.name, layout.opcode
+                ),
+            ));
+        }
+
+        let length = data[1] as usize;
+        if data.len() < length + 2 {
+            return Err(Error::new(
+                ErrorKind::DecodeError,
+                "Packet length != buffer length",
+            ));
+        }
+
+        if length < layout.min_length() {
+            return Err(Error::new(
+                ErrorKind::DecodeError,
+                &format!(
+                    "{} message is too short. Expected at least {:#04X}",
+                    layout.name,
+                    layout.min_length()
+                ),
+            ));
+        }
+
+        let sizes: Vec<usize> = layout
+            .sizes(length + 2)
+            .into_iter()
+            .skip(HEADER.len())
+            .map(|(_, size)| size)
+            .collect();
+
+        let cursor = Cursor {
+            data: &data[..length + 2],
+            offset: HEADER.len(),
+            sizes: sizes.into_iter(),
+        };
+
+        Ok((MessageHeader { opcode, length }, cursor))
+    }
+
+    pub(crate) fn read<T: Wire>(&mut self) -> T {
+        let size = self.sizes.next().unwrap_or_default();
+        let end = (self.offset + size).min(self.data.len());
+        let value = T::read(&self.data[self.offset..end]);
+        self.offset = end;
+        value
+    }
+
+    pub(crate) fn skip(&mut self) {
+        let size = self.sizes.next().unwrap_or_default();
+        self.offset = (self.offset + size).min(self.data.len());
+    }
+}
+
+/// Writes the fields of a message in layout order.
+pub(crate) struct Writer {
+    data: Vec<u8>,
+    length: usize,
+    sizes: std::vec::IntoIter<usize>,
+}
+
+impl Writer {
+    pub(crate) fn new(header: &MessageHeader, layout: &MessageLayout) -> Writer {
+        let sizes: Vec<usize> = layout
+            .sizes(header.length + 2)
+            .into_iter()
+            .skip(HEADER.len())
+            .map(|(_, size)| size)
+            .collect();
+
+        Writer {
+            data: vec![header.opcode, header.length as u8],
+            length: header.length + 2,
+            sizes: sizes.into_iter(),
+        }
+    }
+
+    pub(crate) fn write<T: Wire>(&mut self, value: &T) {
+        let size = self.sizes.next().unwrap_or_default();
+        value.write(size, &mut self.data);
+    }
+
+    /// Writes zeros over a reserved field.
+    pub(crate) fn skip(&mut self) {
+        let size = self.sizes.next().unwrap_or_default();
+        self.data.resize(self.data.len() + size, 0);
+    }
+
+    /// The message, padded or truncated to the length in its header.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.data.resize(self.length, 0);
+        self.data
+    }
+}
+
+/// Declares a message type from its opcode, name and fields in the order they're encoded.
+/// Each field is one of:
+///
+/// - `name: u8`, `u16` or `u32`, a big-endian integer, optionally followed by the bitfields
+///   packed into it as `{ bit: mask, ... }` for dissection
+/// - `name: u32 as 3`, an integer encoded in fewer bytes than its type
+/// - `name: bytes[16]`, a fixed size byte array
+/// - `name: bytes`, the rest of the message
+/// - `name: skip 1`, reserved bytes that decode ignores and encode writes as zeros
+/// - `name: packed { bit: mask, ... }`, a byte whose bitfields are separate struct fields
+///
+/// Every field ends with a comma, and any doc comments come first.
+macro_rules! message {
+    (
+        $(#[$attr:meta])*
+        pub struct $message:ident: $opcode:literal, $name:literal {
+            $($fields:tt)*
+        }
+    ) => {
+        $crate::layout::message!(@munch
+            [$(#[$attr])* $message $opcode $name] [cursor writer]
+            [] [] [] [] [] []
+            $($fields)*
+        );
+    };
+
+    // name: skip 1,
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: skip $size:literal, $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)*]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: Some($size),
+                bits: &[],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)* $cursor.skip();]
+            [$($member)*]
+            [$($encode)* $writer.skip();]
+            [$($row)* "\n- `", stringify!($name), "`: `[u8; ", stringify!($size), "]`, reserved",]
+            $($rest)*
+        );
+    };
+
+    // name: bytes[16],
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: bytes[$size:literal], $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)*
+                $(#[doc = $doc])*
+                #[serde(with = "crate::hex")]
+                #[cfg_attr(feature = "schema", schemars(with = "String"))]
+                pub $name: [u8; $size],
+            ]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: Some($size),
+                bits: &[],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)* let $name = $cursor.read();]
+            [$($member)* $name]
+            [$($encode)* $writer.write($name);]
+            [$($row)* "\n- `", stringify!($name), "`: `[u8; ", stringify!($size), "]`",]
+            $($rest)*
+        );
+    };
+
+    // name: bytes,
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: bytes, $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)*
+                $(#[doc = $doc])*
+                #[serde(with = "crate::hex")]
+                #[cfg_attr(feature = "schema", schemars(with = "String"))]
+                pub $name: Vec<u8>,
+            ]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: None,
+                bits: &[],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)* let $name = $cursor.read();]
+            [$($member)* $name]
+            [$($encode)* $writer.write($name);]
+            [$($row)* "\n- `", stringify!($name), "`: the rest of the message",]
+            $($rest)*
+        );
+    };
+
+    // name: packed { bit: mask, ... },
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: packed { $($bit:ident: $mask:literal),* $(,)? },
+        $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)* $(pub $bit: u8,)*]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: Some(1),
+                bits: &[$($crate::layout::BitField { name: stringify!($bit), mask: $mask },)*],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)*
+                let byte: u8 = $cursor.read();
+                $(let $bit = (byte & $mask) >> ($mask as u8).trailing_zeros();)*
+            ]
+            [$($member)* $($bit)*]
+            [$($encode)*
+                $writer.write(&(0 $(| ((*$bit << ($mask as u8).trailing_zeros()) & $mask))*));
+            ]
+            [$($row)* "\n- `", stringify!($name), "`: `u8` holding" $(, " `", stringify!($bit), "` (`", stringify!($mask), "`)")*,]
+            $($rest)*
+        );
+    };
+
+    // name: u32 as 3,
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: $ty:ident as $size:literal, $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)* $(#[doc = $doc])* pub $name: $ty,]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: Some($size),
+                bits: &[],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)* let $name = $cursor.read();]
+            [$($member)* $name]
+            [$($encode)* $writer.write($name);]
+            [$($row)* "\n- `", stringify!($name), "`: `", stringify!($ty), "` in ", stringify!($size), " bytes",]
+            $($rest)*
+        );
+    };
+
+    // name: u16, or name: u8 { bit: mask, ... },
+    (@munch $head:tt [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+        $(#[doc = $doc:literal])* $name:ident: $ty:ident $({ $($bit:ident: $mask:literal),* $(,)? })?,
+        $($rest:tt)*
+    ) => {
+        $crate::layout::message!(@munch $head [$cursor $writer]
+            [$($struct)* $(#[doc = $doc])* pub $name: $ty,]
+            [$($layout)* $crate::layout::FieldLayout {
+                name: stringify!($name),
+                size: <$ty as $crate::layout::Wire>::SIZE,
+                bits: &[$($($crate::layout::BitField { name: stringify!($bit), mask: $mask },)*)?],
+                doc: concat!("" $(, $doc)*),
+            },]
+            [$($decode)* let $name = $cursor.read();]
+            [$($member)* $name]
+            [$($encode)* $writer.write($name);]
+            [$($row)* "\n- `", stringify!($name), "`: `", stringify!($ty), "`",]
+            $($rest)*
+        );
+    };
+
+    (@munch [$(#[$attr:meta])* $message:ident $opcode:literal $name:literal]
+        [$cursor:ident $writer:ident]
+        [$($struct:tt)*] [$($layout:tt)*] [$($decode:tt)*] [$($member:ident)*]
+        [$($encode:tt)*] [$($row:tt)*]
+    ) => {
+        $(#[$attr])*
+        #[doc = concat!(
+            "\n\nEncoded as the opcode `", stringify!($opcode), "` and the length, followed by:\n",
+            $($row)*
+        )]
+        #[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]
+        #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+        pub struct $message {
+            pub header: $crate::messages::MessageHeader,
+            $($struct)*
+        }
+
+        impl $message {
+            pub const LAYOUT: $crate::layout::MessageLayout = $crate::layout::MessageLayout {
+                opcode: $opcode,
+                name: $name,
+                fields: &[$($layout)*],
+            };
+
+            pub fn decode(data: &[u8]) -> Result<$message, $crate::Error> {
+                let (header, mut $cursor) = $crate::layout::Cursor::new(data, &Self::LAYOUT)?;
+                $($decode)*
+
+                Ok($message { header, $($member),* })
+            }
+
+            pub fn encode(&self) -> Vec<u8> {
+                let $message { header, $($member),* } = self;
+                let mut $writer = $crate::layout::Writer::new(header, &Self::LAYOUT);
+                $($encode)*
+
+                $writer.finish()
+            }
+        }
+
+        impl TryFrom<&[u8]> for $message {
+            type Error = $crate::Error;
+
+            fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+                Self::decode(value)
+            }
+        }
+    };
+}
+
+pub(crate) use message;
@@ -1,15 +1,41 @@
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
+pub mod advertisement;
+pub mod airplay;
 pub mod airprint;
+pub mod battery;
+pub mod btsnoop;
+pub mod capture;
+pub mod dissect;
+pub mod dnssd;
+pub mod events;
+pub mod hex;
+pub mod jsonl;
+pub mod layout;
+pub mod linker;
 pub mod messages;
+pub mod pcap;
 pub mod proximity;
+pub mod ranging;
+pub mod registry;
+pub mod schema;
+pub mod tracker;
+pub mod wireshark;
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum ErrorKind {
     DecodeError,
+    EncodeError,
+    CaptureError,
+    IoError,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Error {
     pub kind: ErrorKind,
     pub message: Option<String>,
@@ -45,3 +71,9 @@ impl From<ErrorKind> for Error {
         }
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::new(ErrorKind::IoError, value.to_string().as_str())
+    }
+}
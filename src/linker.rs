@@ -0,0 +1,302 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{advertisement::Address, messages::Message};
+
+// Largest forward step in the Handoff IV and lid open counter still treated as the same device
+const MAX_IV_STEP: u16 = 64;
+const MAX_LID_STEP: u8 = 3;
+
+const WEIGHT_TIMING: f64 = 1.0;
+const WEIGHT_MODEL: f64 = 2.0;
+const WEIGHT_LID_OPEN_COUNT: f64 = 2.0;
+const WEIGHT_HANDOFF_IV: f64 = 3.0;
+const WEIGHT_NEARBY_INFO: f64 = 1.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LinkerConfig {
+    /// The longest silence between the old address going quiet and the new one appearing.
+    pub max_gap: Duration,
+    /// How long after a new address appears it can still be linked to an older one.
+    pub link_window: Duration,
+    /// The lowest confidence at which two addresses are merged.
+    pub min_confidence: f64,
+}
+
+/// The stable fields seen from a single advertiser address.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AddressEpoch {
+    pub address: Address,
+    pub first_seen: Duration,
+    pub last_seen: Duration,
+    pub device_model: Option<u16>,
+    pub device_color: Option<u8>,
+    pub lid_open_count: Option<u8>,
+    pub handoff_iv: Option<u16>,
+    pub nearby_status_flags: Option<u8>,
+    pub nearby_data_flags: Option<u8>,
+}
+
+/// A device seen under one or more addresses, oldest first.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogicalDevice {
+    pub id: u64,
+    pub addresses: Vec<Address>,
+    pub first_seen: Duration,
+    pub last_seen: Duration,
+    /// The confidence of the weakest link between two of the device's addresses, or 1.0 for a
+    /// device seen under a single address.
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Link {
+    pub device_id: u64,
+    pub previous: Address,
+    pub current: Address,
+    pub confidence: f64,
+}
+
+/// Merges advertiser addresses into logical devices across BLE random address rotations, using
+/// timing continuity and the fields Apple devices keep stable or monotonic between rotations.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AddressLinker {
+    config: LinkerConfig,
+    next_id: u64,
+    epochs: HashMap<Address, AddressEpoch>,
+    devices: HashMap<u64, LogicalDevice>,
+    device_ids: HashMap<Address, u64>,
+}
+
+impl Default for LinkerConfig {
+    fn default() -> Self {
+        LinkerConfig {
+            max_gap: Duration::from_secs(10),
+            link_window: Duration::from_secs(60),
+            min_confidence: 0.6,
+        }
+    }
+}
+
+impl AddressEpoch {
+    fn new(address: Address, timestamp: Duration) -> AddressEpoch {
+        AddressEpoch {
+            address,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            device_model: None,
+            device_color: None,
+            lid_open_count: None,
+            handoff_iv: None,
+            nearby_status_flags: None,
+            nearby_data_flags: None,
+        }
+    }
+
+    fn update(&mut self, timestamp: Duration, message: &Message) {
+        self.first_seen = self.first_seen.min(timestamp);
+        self.last_seen = self.last_seen.max(timestamp);
+
+        match message {
+            Message::ProximityPairing(message) => {
+                self.device_model = Some(message.device_model);
+                self.device_color = Some(message.device_color);
+                self.lid_open_count = Some(message.lid_open_count);
+            }
+            Message::Handoff(message) => self.handoff_iv = Some(message.iv),
+            Message::NearbyInfo(message) => {
+                self.nearby_status_flags = Some(message.status_flags);
+                self.nearby_data_flags = Some(message.data_flags);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl AddressLinker {
+    pub fn new(config: LinkerConfig) -> AddressLinker {
+        AddressLinker {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> &LinkerConfig {
+        &self.config
+    }
+
+    pub fn epoch(&self, address: &Address) -> Option<&AddressEpoch> {
+        self.epochs.get(address)
+    }
+
+    pub fn device_id(&self, address: &Address) -> Option<u64> {
+        self.device_ids.get(address).copied()
+    }
+
+    pub fn device(&self, id: u64) -> Option<&LogicalDevice> {
+        self.devices.get(&id)
+    }
+
+    pub fn device_for(&self, address: &Address) -> Option<&LogicalDevice> {
+        self.device_id(address).and_then(|id| self.device(id))
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &LogicalDevice> {
+        self.devices.values()
+    }
+
+    /// Records a message from `address`, returning the link if this observation was enough to
+    /// merge the address into an earlier device.
+    pub fn observe(
+        &mut self,
+        timestamp: Duration,
+        address: Address,
+        message: &Message,
+    ) -> Option<Link> {
+        self.epochs
+            .entry(address)
+            .or_insert_with(|| AddressEpoch::new(address, timestamp))
+            .update(timestamp, message);
+
+        let id = match self.device_ids.get(&address) {
+            Some(id) => *id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.device_ids.insert(address, id);
+                self.devices.insert(
+                    id,
+                    LogicalDevice {
+                        id,
+                        addresses: vec![address],
+                        first_seen: timestamp,
+                        last_seen: timestamp,
+                        confidence: 1.0,
+                    },
+                );
+                id
+            }
+        };
+
+        let device = self.devices.get_mut(&id)?;
+        device.first_seen = device.first_seen.min(timestamp);
+        device.last_seen = device.last_seen.max(timestamp);
+
+        // Only an address that hasn't been merged yet, and is still new, can start a link
+        let epoch = &self.epochs[&address];
+        if device.addresses.len() > 1
+            || epoch.last_seen.saturating_sub(epoch.first_seen) > self.config.link_window
+        {
+            return None;
+        }
+
+        let (candidate, confidence) = self
+            .devices
+            .values()
+            .filter(|candidate| candidate.id != id)
+            .filter_map(|candidate| {
+                let previous = self.epochs.get(candidate.addresses.last()?)?;
+                let confidence = self.score(previous, epoch)?;
+                Some((candidate.id, confidence))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        if confidence < self.config.min_confidence {
+            return None;
+        }
+
+        self.devices.remove(&id);
+        self.device_ids.insert(address, candidate);
+
+        let device = self.devices.get_mut(&candidate)?;
+        let previous = *device.addresses.last()?;
+        device.addresses.push(address);
+        device.last_seen = device.last_seen.max(timestamp);
+        device.confidence = device.confidence.min(confidence);
+
+        Some(Link {
+            device_id: candidate,
+            previous,
+            current: address,
+            confidence,
+        })
+    }
+
+    /// Forgets addresses and devices not seen since `timestamp`.
+    pub fn forget_before(&mut self, timestamp: Duration) {
+        self.devices
+            .retain(|_, device| device.last_seen >= timestamp);
+        self.epochs.retain(|_, epoch| epoch.last_seen >= timestamp);
+
+        let devices = &self.devices;
+        self.device_ids.retain(|_, id| devices.contains_key(id));
+    }
+
+    /// Scores how likely `current` is the same device as `previous` after an address rotation,
+    /// from 0.0 to 1.0. Returns `None` when the two can't be the same device.
+    fn score(&self, previous: &AddressEpoch, current: &AddressEpoch) -> Option<f64> {
+        if previous.last_seen > current.first_seen {
+            return None;
+        }
+
+        let gap = current.first_seen - previous.last_seen;
+        if gap > self.config.max_gap {
+            return None;
+        }
+
+        let mut evidence = vec![(
+            WEIGHT_TIMING,
+            1.0 - gap.as_secs_f64() / self.config.max_gap.as_secs_f64().max(f64::EPSILON),
+        )];
+
+        if let (Some(a), Some(b)) = (previous.device_model, current.device_model) {
+            if a != b || previous.device_color != current.device_color {
+                return None;
+            }
+
+            evidence.push((WEIGHT_MODEL, 1.0));
+        }
+
+        if let (Some(a), Some(b)) = (previous.lid_open_count, current.lid_open_count) {
+            let step = b.wrapping_sub(a);
+            evidence.push((
+                WEIGHT_LID_OPEN_COUNT,
+                match step {
+                    0 => 1.0,
+                    step if step <= MAX_LID_STEP => 0.8,
+                    _ => 0.0,
+                },
+            ));
+        }
+
+        if let (Some(a), Some(b)) = (previous.handoff_iv, current.handoff_iv) {
+            let step = b.wrapping_sub(a);
+            evidence.push((
+                WEIGHT_HANDOFF_IV,
+                if step <= MAX_IV_STEP { 1.0 } else { 0.0 },
+            ));
+        }
+
+        if let (Some(a), Some(b)) = (previous.nearby_status_flags, current.nearby_status_flags) {
+            let matches =
+                (a == b) as u8 + (previous.nearby_data_flags == current.nearby_data_flags) as u8;
+            evidence.push((WEIGHT_NEARBY_INFO, matches as f64 / 2.0));
+        }
+
+        let weight: f64 = evidence.iter().map(|(weight, _)| weight).sum();
+        let score: f64 = evidence.iter().map(|(weight, score)| weight * score).sum();
+        let confidence = score / weight;
+
+        // Timing alone is weak evidence, addresses rotate at the same time for many devices
+        if evidence.len() == 1 {
+            Some(confidence * 0.5)
+        } else {
+            Some(confidence)
+        }
+    }
+}
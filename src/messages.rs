@@ -1,346 +1,520 @@
-use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Write},
+    ops::Range,
+    str::FromStr,
+};
 
-use crate::{Error, ErrorKind};
+use serde::{Deserialize, Serialize};
 
-// Messages to implement
-enum MessageOpcode {
+use crate::{
+    airplay::AirPlayReceiver,
+    airprint::Printer,
+    dissect::{self, Field},
+    layout::{message, MessageLayout},
+    proximity::ProximityDevice,
+    registry::Registry,
+    Error, ErrorKind,
+};
+
+// Bytes per line in `Message::hex_dump`
+const HEX_DUMP_WIDTH: usize = 8;
+
+/// The type of a Continuity message, from the opcode it starts with. Not every type has a
+/// decoder; see `registry::Registry` for adding one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MessageOpcode {
+    IBeacon = 0x02,
+    Airprint = 0x03,
     AirDrop = 0x05,
     HomeKit = 0x06,
+    ProximityPairing = 0x07,
     HeySiri = 0x08,
+    AirplayTarget = 0x09,
     AirplaySource = 0x0A,
     MagicSwitch = 0x0B,
-    TetheringSource = 0x0E,
+    Handoff = 0x0C,
     TetheringTarget = 0x0D,
+    TetheringSource = 0x0E,
     NearbyAction = 0x0F,
     NearbyInfo = 0x10,
     FindMy = 0x12,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum Message {
     Airprint(AirPrintMessage),
     AirplayTarget(AirplayTargetMessage),
     ProximityPairing(ProximityPairMessage),
     Handoff(HandoffMessage),
     NearbyInfo(NearbyInfoMessage),
+    /// A message decoded by a decoder registered with `registry::Registry`.
+    Custom(CustomMessage),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MessageHeader {
     pub opcode: u8,
     pub length: usize,
 }
 
+/// A message type the crate doesn't decode itself, as returned by user-defined decoders.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProximityPairMessage {
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CustomMessage {
     pub header: MessageHeader,
-    pub device_model: u16,
-    pub device_status: u8,
-    pub battery1: u8,
-    pub battery2: u8,
-    pub lid_open_count: u8,
-    pub device_color: u8,
-    pub encrypted_payload: Vec<u8>,
+    pub name: String,
+    /// The bytes following the length.
+    #[serde(with = "crate::hex")]
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub data: Vec<u8>,
+    /// The message's fields, with byte ranges from the start of the message, as shown by
+    /// `Message::dissect` and `Message::hex_dump`. Empty when the decoder doesn't break the
+    /// message down.
+    pub fields: Vec<Field>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AirPrintMessage {
-    pub header: MessageHeader,
-    pub connection_info: u8,
-    pub rp_index_value: u16,
-    pub port: u16,
-    pub ip4_address: u32,
-    pub ip6_address: u128,
-    pub measured_power: u8,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AirplayTargetMessage {
-    pub header: MessageHeader,
-    pub flags: u8,
-    pub seed: u8,
-    pub ip4_address: u32,
+message! {
+    /// Status of AirPods and Beats headphones, broadcast while the case is open or the
+    /// headphones are out of it.
+    pub struct ProximityPairMessage: 0x07, "Proximity Pairing" {
+        /// 0x01 once paired, 0x07 while in pairing mode.
+        prefix: u8,
+        device_model: u16,
+        device_status: u8 {
+            audio_source: 0xC0,
+            primary_pod: 0x20,
+            secondary_pod_status: 0x18,
+            primary_pod_status: 0x06,
+            case_open: 0x01,
+        },
+        /// Battery levels in tenths of the secondary (high nibble) and primary pod.
+        battery1: u8 {
+            secondary_pod_battery: 0xF0,
+            primary_pod_battery: 0x0F,
+        },
+        /// Charging flags and the case battery level in tenths.
+        battery2: u8 {
+            unknown: 0x80,
+            case_charging: 0x40,
+            secondary_pod_charging: 0x20,
+            primary_pod_charging: 0x10,
+            case_battery: 0x0F,
+        },
+        lid_open_count: u8,
+        device_color: u8,
+        reserved: skip 1,
+        encrypted_payload: bytes,
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HandoffMessage {
-    pub header: MessageHeader,
-    pub clipboard_status: u8,
-    pub iv: u16,
-    pub gcm_auth: u8,
-    pub encrypted_payload: Vec<u8>,
+message! {
+    /// A printer advertising how to reach it over IPP.
+    pub struct AirPrintMessage: 0x03, "AirPrint" {
+        connection_info: u8 {
+            address_type: 0xF0,
+            resource_type: 0x0C,
+            security_type: 0x03,
+        },
+        /// Index of the resource path, or the printer ID, depending on the resource type.
+        rp_index_value: u16,
+        port: u16,
+        /// An IPv6 address, or an IPv4 address in the first four bytes.
+        address: bytes[16],
+        /// Measured TX power at one meter, as a signed dBm value.
+        measured_power: u8,
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NearbyInfoMessage {
-    pub header: MessageHeader,
-    pub status_flags: u8,
-    pub action_code: u8,
-    pub data_flags: u8,
-    pub auth_tag: u32,
+message! {
+    /// An AirPlay receiver such as an Apple TV or AirPlay speaker.
+    pub struct AirplayTargetMessage: 0x09, "AirPlay Target" {
+        flags: u8 {
+            access_control: 0x08,
+            pin_required: 0x04,
+            password_required: 0x02,
+            screen_mirroring: 0x01,
+        },
+        /// Changes whenever the receiver's configuration changes.
+        seed: u8,
+        ip4_address: u32,
+    }
 }
 
-impl Message {
-    pub fn decode(data: &[u8]) -> Result<Message, Error> {
-        match data[0] {
-            0x07 => Ok(Message::ProximityPairing(ProximityPairMessage::decode(
-                data,
-            )?)),
-            0x09 => Ok(Message::AirplayTarget(AirplayTargetMessage::decode(data)?)),
-            0x03 => Ok(Message::Airprint(AirPrintMessage::decode(data)?)),
-            0x0C => Ok(Message::Handoff(HandoffMessage::decode(data)?)),
-            0x10 => Ok(Message::NearbyInfo(NearbyInfoMessage::decode(data)?)),
-            _ => Err(Error::new(
-                ErrorKind::DecodeError,
-                format!("Unknown opcode: {:02X?}", data[0]).as_str(),
-            )),
-        }
+message! {
+    pub struct HandoffMessage: 0x0C, "Handoff" {
+        clipboard_status: u8,
+        /// Sequence number, used as the IV of the encrypted payload.
+        iv: u16,
+        /// The first byte of the AES-GCM authentication tag.
+        gcm_auth: u8,
+        encrypted_payload: bytes,
     }
 }
 
-impl TryFrom<&[u8]> for Message {
-    type Error = crate::Error;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        Self::decode(value)
+message! {
+    pub struct NearbyInfoMessage: 0x10, "Nearby Info" {
+        status_action: packed {
+            status_flags: 0xF0,
+            action_code: 0x0F,
+        },
+        data_flags: u8,
+        auth_tag: u32 as 3,
     }
 }
 
-impl ProximityPairMessage {
-    pub fn decode(data: &[u8]) -> Result<ProximityPairMessage, Error> {
-        if data.len() < 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Length mismatch. Cannot read opcode + length",
-            ));
-        }
-
-        let opcode = data[0];
-        if opcode != 0x07 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Tried to decode Proximity Pair message with invalid opcode. Expected 0x07",
-            ));
+impl MessageOpcode {
+    pub const ALL: [MessageOpcode; 15] = [
+        MessageOpcode::IBeacon,
+        MessageOpcode::Airprint,
+        MessageOpcode::AirDrop,
+        MessageOpcode::HomeKit,
+        MessageOpcode::ProximityPairing,
+        MessageOpcode::HeySiri,
+        MessageOpcode::AirplayTarget,
+        MessageOpcode::AirplaySource,
+        MessageOpcode::MagicSwitch,
+        MessageOpcode::Handoff,
+        MessageOpcode::TetheringTarget,
+        MessageOpcode::TetheringSource,
+        MessageOpcode::NearbyAction,
+        MessageOpcode::NearbyInfo,
+        MessageOpcode::FindMy,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MessageOpcode::IBeacon => "iBeacon",
+            MessageOpcode::Airprint => AirPrintMessage::LAYOUT.name,
+            MessageOpcode::AirDrop => "AirDrop",
+            MessageOpcode::HomeKit => "HomeKit",
+            MessageOpcode::ProximityPairing => ProximityPairMessage::LAYOUT.name,
+            MessageOpcode::HeySiri => "Hey Siri",
+            MessageOpcode::AirplayTarget => AirplayTargetMessage::LAYOUT.name,
+            MessageOpcode::AirplaySource => "AirPlay Source",
+            MessageOpcode::MagicSwitch => "Magic Switch",
+            MessageOpcode::Handoff => HandoffMessage::LAYOUT.name,
+            MessageOpcode::TetheringTarget => "Tethering Target",
+            MessageOpcode::TetheringSource => "Tethering Source",
+            MessageOpcode::NearbyAction => "Nearby Action",
+            MessageOpcode::NearbyInfo => NearbyInfoMessage::LAYOUT.name,
+            MessageOpcode::FindMy => "Find My",
         }
+    }
 
-        let length = data[1] as usize;
-        if data.len() < length + 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Packet length != buffer length",
-            ));
+    /// The length byte messages of this type are seen with, or `None` when it varies between
+    /// devices and OS versions.
+    pub fn expected_length(&self) -> Option<usize> {
+        match self {
+            MessageOpcode::IBeacon => Some(0x15),
+            MessageOpcode::Airprint => Some(0x16),
+            MessageOpcode::AirDrop => Some(0x12),
+            MessageOpcode::HomeKit => Some(0x0D),
+            MessageOpcode::HeySiri => Some(0x07),
+            MessageOpcode::AirplayTarget => Some(0x06),
+            MessageOpcode::AirplaySource => Some(0x01),
+            MessageOpcode::MagicSwitch => Some(0x03),
+            MessageOpcode::Handoff => Some(0x0E),
+            MessageOpcode::TetheringTarget => Some(0x04),
+            MessageOpcode::TetheringSource => Some(0x06),
+            MessageOpcode::ProximityPairing
+            | MessageOpcode::NearbyAction
+            | MessageOpcode::NearbyInfo
+            | MessageOpcode::FindMy => None,
         }
-
-        let mut encrypted_payload = vec![0; length - 11];
-        encrypted_payload.copy_from_slice(&data[11..length]);
-
-        Ok(ProximityPairMessage {
-            header: MessageHeader { opcode, length },
-            device_model: ((data[3] as u16) << 8) + (data[4] as u16),
-            device_status: data[5],
-            battery1: data[6],
-            battery2: data[7],
-            lid_open_count: data[8],
-            device_color: data[9],
-            encrypted_payload,
-        })
     }
 }
 
-impl TryFrom<&[u8]> for ProximityPairMessage {
+impl TryFrom<u8> for MessageOpcode {
     type Error = crate::Error;
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        Self::decode(value)
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        MessageOpcode::ALL
+            .into_iter()
+            .find(|opcode| *opcode as u8 == value)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::DecodeError,
+                    format!("Unknown opcode: {:02X?}", value).as_str(),
+                )
+            })
     }
 }
 
-impl AirPrintMessage {
-    pub fn decode(data: &[u8]) -> Result<AirPrintMessage, Error> {
-        if data.len() < 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Length mismatch. Cannot read opcode + length",
-            ));
-        }
-
-        let opcode = data[0];
-        if opcode != 0x03 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Tried to decode AirPrint message with invalid opcode. Expected 0x03",
-            ));
-        }
+impl From<MessageOpcode> for u8 {
+    fn from(value: MessageOpcode) -> Self {
+        value as u8
+    }
+}
 
-        let length = data[1] as usize;
-        if data.len() < length + 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Packet length != buffer length",
-            ));
+impl FromStr for MessageOpcode {
+    type Err = crate::Error;
+
+    /// Parses an opcode in hex, such as `0x07`, or a name such as `proximity_pairing` or
+    /// `Proximity Pairing`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace([' ', '-'], "_");
+        if let Some(opcode) = MessageOpcode::ALL
+            .into_iter()
+            .find(|opcode| opcode.name().to_lowercase().replace(' ', "_") == name)
+        {
+            return Ok(opcode);
         }
 
-        Ok(AirPrintMessage {
-            header: MessageHeader { opcode, length },
-            connection_info: data[2],
-            rp_index_value: ((data[3] as u16) << 8) + (data[4] as u16),
-            port: ((data[5] as u16) << 8) + (data[6] as u16),
-            ip4_address: ((data[7] as u32) << 24)
-                + ((data[8] as u32) << 16)
-                + ((data[9] as u32) << 8)
-                + (data[10] as u32),
-            ip6_address: ((data[11] as u128) << 88)
-                + ((data[12] as u128) << 80)
-                + ((data[13] as u128) << 72)
-                + ((data[14] as u128) << 64)
-                + ((data[15] as u128) << 56)
-                + ((data[16] as u128) << 48)
-                + ((data[17] as u128) << 40)
-                + ((data[18] as u128) << 32)
-                + ((data[19] as u128) << 24)
-                + ((data[20] as u128) << 16)
-                + ((data[21] as u128) << 8)
-                + (data[22] as u128),
-            measured_power: data[23],
-        })
+        let digits = name.trim_start_matches("0x");
+        u8::from_str_radix(digits, 16)
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::DecodeError,
+                    format!("Unknown message type: {}", s).as_str(),
+                )
+            })
+            .and_then(MessageOpcode::try_from)
     }
 }
 
-impl TryFrom<&[u8]> for AirPrintMessage {
-    type Error = crate::Error;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        Self::decode(value)
+impl Display for MessageOpcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
     }
 }
 
-impl AirplayTargetMessage {
-    pub fn decode(data: &[u8]) -> Result<AirplayTargetMessage, Error> {
-        if data.len() < 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Length mismatch. Cannot read opcode + length",
-            ));
+impl Message {
+    /// Decodes `data` with the decoder installed for its opcode, which for the built-in
+    /// message types is their own `decode` unless overridden through `registry::Registry`.
+    pub fn decode(data: &[u8]) -> Result<Message, Error> {
+        Registry::decode_global(data)
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            Message::Airprint(message) => &message.header,
+            Message::AirplayTarget(message) => &message.header,
+            Message::ProximityPairing(message) => &message.header,
+            Message::Handoff(message) => &message.header,
+            Message::NearbyInfo(message) => &message.header,
+            Message::Custom(message) => &message.header,
         }
+    }
 
-        let opcode = data[0];
-        if opcode != 0x09 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Tried to decode Airplay Target message with invalid opcode. Expected 0x09",
-            ));
+    /// The message type, or `None` for custom messages with an opcode the crate doesn't know.
+    pub fn opcode(&self) -> Option<MessageOpcode> {
+        MessageOpcode::try_from(self.header().opcode).ok()
+    }
+
+    pub fn name(&self) -> &str {
+        match (self, self.layout()) {
+            (Message::Custom(message), _) => &message.name,
+            (_, Some(layout)) => layout.name,
+            _ => "",
         }
+    }
 
-        let length = data[1] as usize;
-        if data.len() < length + 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Packet length != buffer length",
-            ));
+    /// The layout of the built-in message types, `None` for custom messages.
+    pub fn layout(&self) -> Option<&'static MessageLayout> {
+        match self {
+            Message::Airprint(_) => Some(&AirPrintMessage::LAYOUT),
+            Message::AirplayTarget(_) => Some(&AirplayTargetMessage::LAYOUT),
+            Message::ProximityPairing(_) => Some(&ProximityPairMessage::LAYOUT),
+            Message::Handoff(_) => Some(&HandoffMessage::LAYOUT),
+            Message::NearbyInfo(_) => Some(&NearbyInfoMessage::LAYOUT),
+            Message::Custom(_) => None,
         }
+    }
 
-        Ok(AirplayTargetMessage {
-            header: MessageHeader { opcode, length },
-            flags: data[2],
-            seed: data[3],
-            ip4_address: ((data[4] as u32) << 24)
-                + ((data[5] as u32) << 16)
-                + ((data[6] as u32) << 8)
-                + (data[7] as u32),
-        })
+    /// The name and byte range of each field in a message `length` bytes long, in the order
+    /// they are encoded.
+    fn ranges(&self, length: usize) -> Vec<(&str, Range<usize>)> {
+        let ranges: Vec<(&str, Range<usize>)> = match (self, self.layout()) {
+            (_, Some(layout)) => {
+                let mut offset = 0;
+                layout
+                    .sizes(length)
+                    .into_iter()
+                    .map(|(field, size)| {
+                        offset += size;
+                        (field.name, offset - size..offset)
+                    })
+                    .collect()
+            }
+            (Message::Custom(message), None) if !message.fields.is_empty() => message
+                .fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.range.clone()))
+                .collect(),
+            _ => vec![("opcode", 0..1), ("length", 1..2), ("data", 2..length)],
+        };
+
+        ranges
+            .into_iter()
+            .map(|(name, range)| (name, range.start.min(length)..range.end.min(length)))
+            .collect()
     }
-}
 
-impl TryFrom<&[u8]> for AirplayTargetMessage {
-    type Error = crate::Error;
+    /// Decodes `data` and breaks it down into a tree of fields, including the bitfields packed
+    /// into single bytes, with their byte ranges, raw values and interpretations.
+    pub fn dissect(data: &[u8]) -> Result<Field, Error> {
+        let message = Message::decode(data)?;
+        let data = &data[..message.header().length + 2];
+
+        let mut children: Vec<Field> = match (&message, message.layout()) {
+            (_, Some(layout)) => layout
+                .sizes(data.len())
+                .into_iter()
+                .zip(message.ranges(data.len()))
+                .map(|((field, _), (_, range))| dissect::field(&message, field, data, range))
+                .collect(),
+            (Message::Custom(custom), None) if !custom.fields.is_empty() => custom.fields.clone(),
+            _ => message
+                .ranges(data.len())
+                .into_iter()
+                .map(|(name, range)| Field::new(name, data, range))
+                .collect(),
+        };
+
+        let used = children
+            .iter()
+            .map(|child| child.range.end)
+            .max()
+            .unwrap_or_default();
+        if used < data.len() {
+            children.push(Field::new("padding", data, used..data.len()));
+        }
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        Self::decode(value)
+        Ok(Field::new(message.name(), data, 0..data.len()).children(children))
     }
-}
 
-impl HandoffMessage {
-    pub fn decode(data: &[u8]) -> Result<HandoffMessage, Error> {
-        if data.len() < 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Length mismatch. Cannot read opcode + length",
-            ));
+    /// The encoded message with each field's bytes labelled by its name, one field per line.
+    pub fn hex_dump(&self) -> String {
+        let data = self.encode();
+        let mut ranges = self.ranges(data.len());
+        let used = ranges
+            .iter()
+            .map(|(_, range)| range.end)
+            .max()
+            .unwrap_or_default();
+        if used < data.len() {
+            ranges.push(("padding", used..data.len()));
         }
 
-        let opcode = data[0];
-        if opcode != 0x0C {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Tried to decode Handoff message with invalid opcode. Expected 0x0C",
-            ));
+        let mut dump = String::new();
+        for (name, range) in ranges {
+            for (line, chunk) in data[range.clone()].chunks(HEX_DUMP_WIDTH).enumerate() {
+                let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02X}", byte)).collect();
+                let row = format!(
+                    "{:02X}  {:<width$}  {}",
+                    range.start + line * HEX_DUMP_WIDTH,
+                    hex.join(" "),
+                    if line == 0 { name } else { "" },
+                    width = HEX_DUMP_WIDTH * 3 - 1
+                );
+                let _ = writeln!(dump, "{}", row.trim_end());
+            }
         }
 
-        let length = data[1] as usize;
-        if data.len() < length + 2 {
+        dump
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Message::Airprint(message) => message.encode(),
+            Message::AirplayTarget(message) => message.encode(),
+            Message::ProximityPairing(message) => message.encode(),
+            Message::Handoff(message) => message.encode(),
+            Message::NearbyInfo(message) => message.encode(),
+            Message::Custom(message) => message.encode(),
+        }
+    }
+}
+
+impl CustomMessage {
+    /// A message named `name` without fields, from its encoded bytes starting with the opcode
+    /// and length.
+    pub fn new(name: &str, data: &[u8]) -> Result<CustomMessage, Error> {
+        if data.len() < 2 || data.len() < data[1] as usize + 2 {
             return Err(Error::new(
                 ErrorKind::DecodeError,
                 "Packet length != buffer length",
             ));
         }
 
-        let mut encrypted_payload = vec![0; length - 6];
-        encrypted_payload.copy_from_slice(&data[6..length]);
-
-        Ok(HandoffMessage {
-            header: MessageHeader { opcode, length },
-            clipboard_status: data[2],
-            iv: ((data[3] as u16) << 8) + (data[4] as u16),
-            gcm_auth: data[5],
-            encrypted_payload,
+        let length = data[1] as usize;
+        Ok(CustomMessage {
+            header: MessageHeader {
+                opcode: data[0],
+                length,
+            },
+            name: name.to_string(),
+            data: data[2..length + 2].to_vec(),
+            fields: Vec::new(),
         })
     }
-}
 
-impl TryFrom<&[u8]> for HandoffMessage {
-    type Error = crate::Error;
+    pub fn fields(mut self, fields: Vec<Field>) -> CustomMessage {
+        self.fields = fields;
+        self
+    }
 
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        Self::decode(value)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = vec![self.header.opcode, self.header.length as u8];
+        data.extend_from_slice(&self.data);
+        data.resize(self.header.length + 2, 0);
+        data
     }
 }
 
-impl NearbyInfoMessage {
-    pub fn decode(data: &[u8]) -> Result<NearbyInfoMessage, Error> {
-        if data.len() < 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Length mismatch. Cannot read opcode + length",
-            ));
+impl Display for Message {
+    /// The message name followed by its interpretation, such as `AirPlay Target: 192.168.1.2,
+    /// screen mirroring, seed 05`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.name())?;
+
+        match self {
+            Message::ProximityPairing(message) => {
+                write!(f, "{}", ProximityDevice::from(message.clone()))
+            }
+            Message::Airprint(message) => write!(f, "{}", Printer::from(message.clone())),
+            Message::AirplayTarget(message) => {
+                write!(f, "{}", AirPlayReceiver::from(message.clone()))
+            }
+            Message::Handoff(message) => write!(f, "{}", message),
+            Message::NearbyInfo(message) => write!(f, "{}", message),
+            Message::Custom(message) => write!(f, "{}", message),
         }
+    }
+}
 
-        let opcode = data[0];
-        if opcode != 0x10 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Tried to decode Nearby Info message with invalid opcode. Expected 0x10",
-            ));
-        }
+impl Display for HandoffMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "clipboard {:02X}, IV {:04X}, auth {:02X}",
+            self.clipboard_status, self.iv, self.gcm_auth
+        )
+    }
+}
 
-        let length = data[1] as usize;
-        if data.len() < length + 2 {
-            return Err(Error::new(
-                ErrorKind::DecodeError,
-                "Packet length != buffer length",
-            ));
-        }
+impl Display for NearbyInfoMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "status {:X}, action {:X}, data flags {:02X}",
+            self.status_flags, self.action_code, self.data_flags
+        )
+    }
+}
 
-        Ok(NearbyInfoMessage {
-            header: MessageHeader { opcode, length },
-            status_flags: data[2] >> 4,
-            action_code: data[2] & 0x0F,
-            data_flags: data[3],
-            auth_tag: ((data[4] as u32) << 16) + ((data[4] as u32) << 8) + (data[4] as u32),
-        })
+impl Display for CustomMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", crate::hex::encode(&self.data))
     }
 }
 
-impl TryFrom<&[u8]> for NearbyInfoMessage {
+impl TryFrom<&[u8]> for Message {
     type Error = crate::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
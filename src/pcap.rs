@@ -0,0 +1,602 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    advertisement::{Address, Advertisement, PduType, MAX_ADVERTISING_DATA_LENGTH},
+    Error, ErrorKind,
+};
+
+pub const LINKTYPE_BLUETOOTH_LE_LL: u32 = 251;
+pub const LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR: u32 = 256;
+
+pub const ADVERTISING_ACCESS_ADDRESS: u32 = 0x8E89BED6;
+
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B23C4D;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x00000001;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x00000003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x00000006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+const PCAPNG_OPTION_END: u16 = 0;
+const PCAPNG_OPTION_TSRESOL: u16 = 9;
+
+const PHDR_LENGTH: usize = 10;
+const PHDR_DEWHITENED: u16 = 0x0001;
+const PHDR_SIGNAL_POWER_VALID: u16 = 0x0002;
+const PHDR_REFERENCE_ACCESS_ADDRESS_VALID: u16 = 0x0010;
+const PHDR_CRC_CHECKED: u16 = 0x0400;
+const PHDR_CRC_VALID: u16 = 0x0800;
+
+// BLE packets are at most a few hundred bytes; anything larger is a corrupt length field
+const MAX_RECORD_LENGTH: usize = 256 * 1024;
+
+const ADVERTISING_CRC_INIT: u32 = 0x555555;
+const DEFAULT_CHANNEL: u8 = 37;
+
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    linktype: u32,
+    units_per_second: u64,
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        big_endian: bool,
+        units_per_second: u64,
+        linktype: u32,
+    },
+    PcapNg {
+        big_endian: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+struct Packet {
+    timestamp: Duration,
+    linktype: u32,
+    data: Vec<u8>,
+}
+
+/// Reads BLE advertisements out of pcap and pcapng captures using the BLE link-layer DLTs.
+pub struct PcapReader<R: Read> {
+    reader: R,
+    format: Format,
+    done: bool,
+}
+
+impl PcapReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        let format = if u32::from_le_bytes(magic) == PCAPNG_SECTION_HEADER {
+            let big_endian = read_section_header(&mut reader)?;
+            Format::PcapNg {
+                big_endian,
+                interfaces: Vec::new(),
+            }
+        } else {
+            let (big_endian, units_per_second) =
+                match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+                    (PCAP_MAGIC_MICROS, _) => (false, 1_000_000),
+                    (PCAP_MAGIC_NANOS, _) => (false, 1_000_000_000),
+                    (_, PCAP_MAGIC_MICROS) => (true, 1_000_000),
+                    (_, PCAP_MAGIC_NANOS) => (true, 1_000_000_000),
+                    _ => {
+                        return Err(Error::new(
+                            ErrorKind::CaptureError,
+                            "Not a pcap or pcapng capture",
+                        ))
+                    }
+                };
+
+            let mut header = [0; 20];
+            reader.read_exact(&mut header)?;
+
+            Format::Pcap {
+                big_endian,
+                units_per_second,
+                linktype: read_u32(&header[16..20], big_endian),
+            }
+        };
+
+        Ok(PcapReader {
+            reader,
+            format,
+            done: false,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Option<Packet>, Error> {
+        match &mut self.format {
+            Format::Pcap {
+                big_endian,
+                units_per_second,
+                linktype,
+            } => {
+                let mut header = [0; 16];
+                if !read_exact_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+
+                let seconds = read_u32(&header[0..4], *big_endian) as u64;
+                let fraction = read_u32(&header[4..8], *big_endian) as u64;
+                let captured_length = read_u32(&header[8..12], *big_endian) as usize;
+                if captured_length > MAX_RECORD_LENGTH {
+                    return Err(Error::new(
+                        ErrorKind::CaptureError,
+                        format!("pcap record is too long: {} bytes", captured_length).as_str(),
+                    ));
+                }
+
+                let mut data = vec![0; captured_length];
+                self.reader.read_exact(&mut data)?;
+
+                Ok(Some(Packet {
+                    timestamp: Duration::from_secs(seconds)
+                        + Duration::from_nanos(fraction * 1_000_000_000 / *units_per_second),
+                    linktype: *linktype,
+                    data,
+                }))
+            }
+            Format::PcapNg {
+                big_endian,
+                interfaces,
+            } => loop {
+                let mut header = [0; 8];
+                if !read_exact_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+
+                let block_type = read_u32(&header[0..4], *big_endian);
+                if block_type == PCAPNG_SECTION_HEADER {
+                    // The length field can only be read once the byte order magic is known
+                    let mut magic = [0; 4];
+                    self.reader.read_exact(&mut magic)?;
+                    *big_endian = u32::from_be_bytes(magic) == PCAPNG_BYTE_ORDER_MAGIC;
+                    interfaces.clear();
+
+                    let total_length = read_u32(&header[4..8], *big_endian) as usize;
+                    skip(&mut self.reader, total_length.saturating_sub(12))?;
+                    continue;
+                }
+
+                let total_length = read_u32(&header[4..8], *big_endian) as usize;
+                if total_length < 12 {
+                    return Err(Error::new(
+                        ErrorKind::CaptureError,
+                        "pcapng block is shorter than its header",
+                    ));
+                }
+                if total_length > MAX_RECORD_LENGTH {
+                    return Err(Error::new(
+                        ErrorKind::CaptureError,
+                        format!("pcapng block is too long: {} bytes", total_length).as_str(),
+                    ));
+                }
+
+                let mut body = vec![0; total_length - 8];
+                self.reader.read_exact(&mut body)?;
+                let body = &body[..body.len() - 4];
+
+                match block_type {
+                    PCAPNG_INTERFACE_DESCRIPTION => {
+                        interfaces.push(read_interface(body, *big_endian)?);
+                    }
+                    PCAPNG_ENHANCED_PACKET => {
+                        if body.len() < 20 {
+                            return Err(Error::new(
+                                ErrorKind::CaptureError,
+                                "Enhanced packet block is too short",
+                            ));
+                        }
+
+                        let interface = read_u32(&body[0..4], *big_endian) as usize;
+                        let interface = *interfaces.get(interface).ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::CaptureError,
+                                "Enhanced packet block references an unknown interface",
+                            )
+                        })?;
+
+                        let units = ((read_u32(&body[4..8], *big_endian) as u64) << 32)
+                            + read_u32(&body[8..12], *big_endian) as u64;
+                        let captured_length = read_u32(&body[12..16], *big_endian) as usize;
+                        let data = body.get(20..20 + captured_length).ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::CaptureError,
+                                "Enhanced packet block data exceeds block length",
+                            )
+                        })?;
+
+                        return Ok(Some(Packet {
+                            timestamp: timestamp_from_units(units, interface.units_per_second),
+                            linktype: interface.linktype,
+                            data: data.to_vec(),
+                        }));
+                    }
+                    PCAPNG_SIMPLE_PACKET => {
+                        let interface = *interfaces.first().ok_or_else(|| {
+                            Error::new(
+                                ErrorKind::CaptureError,
+                                "Simple packet block without an interface",
+                            )
+                        })?;
+
+                        if body.len() < 4 {
+                            return Err(Error::new(
+                                ErrorKind::CaptureError,
+                                "Simple packet block is too short",
+                            ));
+                        }
+
+                        let original_length = read_u32(&body[0..4], *big_endian) as usize;
+                        let data = &body[4..];
+
+                        return Ok(Some(Packet {
+                            timestamp: Duration::ZERO,
+                            linktype: interface.linktype,
+                            data: data[..original_length.min(data.len())].to_vec(),
+                        }));
+                    }
+                    _ => continue,
+                }
+            },
+        }
+    }
+}
+
+/// Writes advertisements to a pcap capture using `LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR`, so they
+/// can be opened in Wireshark or read back with `PcapReader`.
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl PcapWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> PcapWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self, Error> {
+        writer.write_all(&PCAP_MAGIC_MICROS.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&4u16.to_le_bytes())?;
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&(u16::MAX as u32).to_le_bytes())?;
+        writer.write_all(&LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR.to_le_bytes())?;
+
+        Ok(PcapWriter { writer })
+    }
+
+    /// Writes `advertisement` as a single packet. The channel defaults to 37 when unset and
+    /// the signal power is only marked valid when an RSSI is present. Fails, writing nothing,
+    /// when `encode_packet` does.
+    pub fn write(&mut self, advertisement: &Advertisement) -> Result<(), Error> {
+        let packet = encode_packet(advertisement)?;
+
+        self.writer
+            .write_all(&(advertisement.timestamp.as_secs() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&advertisement.timestamp.subsec_micros().to_le_bytes())?;
+        self.writer
+            .write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&packet)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Advertisement, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.next_packet() {
+                Ok(Some(packet)) => {
+                    if let Some(advertisement) =
+                        decode_packet(packet.linktype, &packet.data, packet.timestamp)
+                    {
+                        return Some(Ok(advertisement));
+                    }
+                }
+                Ok(None) => self.done = true,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Decodes a single BLE link-layer packet, returning `None` for anything that isn't an
+/// advertising PDU carrying advertising data.
+pub fn decode_packet(linktype: u32, data: &[u8], timestamp: Duration) -> Option<Advertisement> {
+    let (channel, rssi, packet) = match linktype {
+        LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR => {
+            if data.len() < PHDR_LENGTH {
+                return None;
+            }
+
+            let flags = u16::from_le_bytes([data[8], data[9]]);
+            let rssi = if flags & PHDR_SIGNAL_POWER_VALID != 0 {
+                Some(data[1] as i8)
+            } else {
+                None
+            };
+
+            (channel_from_rf_index(data[0]), rssi, &data[PHDR_LENGTH..])
+        }
+        LINKTYPE_BLUETOOTH_LE_LL => (None, None, data),
+        _ => return None,
+    };
+
+    if packet.len() < 6 {
+        return None;
+    }
+
+    let access_address = u32::from_le_bytes([packet[0], packet[1], packet[2], packet[3]]);
+    if access_address != ADVERTISING_ACCESS_ADDRESS {
+        return None;
+    }
+
+    let pdu_type = PduType::from(packet[4]);
+    let length = packet[5] as usize;
+    if !pdu_type.has_adv_data() || length < 6 || packet.len() < 6 + length {
+        return None;
+    }
+
+    let payload = &packet[6..6 + length];
+
+    Some(Advertisement {
+        timestamp,
+        address: Address::from_le_bytes(&payload[0..6]),
+        random_address: packet[4] & 0x40 != 0,
+        pdu_type,
+        rssi,
+        channel,
+        data: payload[6..].to_vec(),
+    })
+}
+
+/// Maps the RF channel index in the PHDR, counted up from 2402 MHz in 2 MHz steps, to the BLE
+/// channel number: the advertising channels 37, 38 and 39 sit at RF indexes 0, 12 and 39.
+pub fn channel_from_rf_index(rf_index: u8) -> Option<u8> {
+    match rf_index {
+        0 => Some(37),
+        1..=11 => Some(rf_index - 1),
+        12 => Some(38),
+        13..=38 => Some(rf_index - 2),
+        39 => Some(39),
+        _ => None,
+    }
+}
+
+/// The RF channel index written to the PHDR for a BLE channel number, the inverse of
+/// `channel_from_rf_index`.
+pub fn rf_index_from_channel(channel: u8) -> Option<u8> {
+    match channel {
+        0..=10 => Some(channel + 1),
+        11..=36 => Some(channel + 2),
+        37 => Some(0),
+        38 => Some(12),
+        39 => Some(39),
+        _ => None,
+    }
+}
+
+/// Encodes an advertisement as a `LINKTYPE_BLUETOOTH_LE_LL_WITH_PHDR` packet, the inverse of
+/// `decode_packet`. Fails when the advertising data doesn't fit a legacy advertising PDU or
+/// the channel doesn't exist.
+pub fn encode_packet(advertisement: &Advertisement) -> Result<Vec<u8>, Error> {
+    if advertisement.data.len() > MAX_ADVERTISING_DATA_LENGTH {
+        return Err(Error::new(
+            ErrorKind::EncodeError,
+            format!(
+                "Advertising data is {} bytes, more than {}",
+                advertisement.data.len(),
+                MAX_ADVERTISING_DATA_LENGTH
+            )
+            .as_str(),
+        ));
+    }
+
+    let channel = advertisement.channel.unwrap_or(DEFAULT_CHANNEL);
+    let rf_index = rf_index_from_channel(channel).ok_or_else(|| {
+        Error::new(
+            ErrorKind::EncodeError,
+            format!("Invalid channel: {}", channel).as_str(),
+        )
+    })?;
+
+    let mut flags =
+        PHDR_DEWHITENED | PHDR_REFERENCE_ACCESS_ADDRESS_VALID | PHDR_CRC_CHECKED | PHDR_CRC_VALID;
+    if advertisement.rssi.is_some() {
+        flags |= PHDR_SIGNAL_POWER_VALID;
+    }
+
+    let mut packet = vec![rf_index, advertisement.rssi.unwrap_or(0) as u8, 0, 0];
+    packet.extend_from_slice(&ADVERTISING_ACCESS_ADDRESS.to_le_bytes());
+    packet.extend_from_slice(&flags.to_le_bytes());
+    packet.extend_from_slice(&ADVERTISING_ACCESS_ADDRESS.to_le_bytes());
+
+    let mut pdu = vec![
+        (advertisement.pdu_type as u8 & 0x0F)
+            | if advertisement.random_address {
+                0x40
+            } else {
+                0
+            },
+        (6 + advertisement.data.len()) as u8,
+    ];
+    pdu.extend_from_slice(&advertisement.address.to_le_bytes());
+    pdu.extend_from_slice(&advertisement.data);
+
+    let crc = crc24(&pdu);
+    packet.extend(pdu);
+    packet.extend_from_slice(&crc.to_le_bytes()[..3]);
+    Ok(packet)
+}
+
+/// The link-layer CRC, computed over the PDU with the bit-reversed advertising CRC init. The
+/// result is transmitted least significant byte first.
+fn crc24(data: &[u8]) -> u32 {
+    let mut state = ADVERTISING_CRC_INIT.reverse_bits() >> 8;
+    for byte in data {
+        let mut byte = *byte;
+        for _ in 0..8 {
+            let next_bit = (state ^ byte as u32) & 0x01;
+            byte >>= 1;
+            state >>= 1;
+            if next_bit != 0 {
+                state |= 1 << 23;
+                state ^= 0x5A6000;
+            }
+        }
+    }
+
+    state
+}
+
+fn read_section_header<R: Read>(reader: &mut R) -> Result<bool, Error> {
+    let mut header = [0; 8];
+    reader.read_exact(&mut header)?;
+
+    let big_endian = match u32::from_le_bytes([header[4], header[5], header[6], header[7]]) {
+        PCAPNG_BYTE_ORDER_MAGIC => false,
+        magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::CaptureError,
+                "Invalid pcapng byte order magic",
+            ))
+        }
+    };
+
+    // Skip the remainder of the block: block type and length, byte order magic and trailer
+    let total_length = read_u32(&header[0..4], big_endian) as usize;
+    skip(reader, total_length.saturating_sub(12))?;
+
+    Ok(big_endian)
+}
+
+fn read_interface(body: &[u8], big_endian: bool) -> Result<Interface, Error> {
+    if body.len() < 8 {
+        return Err(Error::new(
+            ErrorKind::CaptureError,
+            "Interface description block is too short",
+        ));
+    }
+
+    let mut interface = Interface {
+        linktype: read_u16(&body[0..2], big_endian) as u32,
+        units_per_second: 1_000_000,
+    };
+
+    let mut offset = 8;
+    while offset + 4 <= body.len() {
+        let code = read_u16(&body[offset..offset + 2], big_endian);
+        let length = read_u16(&body[offset + 2..offset + 4], big_endian) as usize;
+        if code == PCAPNG_OPTION_END {
+            break;
+        }
+
+        if code == PCAPNG_OPTION_TSRESOL && length >= 1 && offset + 4 < body.len() {
+            let resolution = body[offset + 4];
+            interface.units_per_second = if resolution & 0x80 == 0 {
+                10u64.saturating_pow(resolution as u32)
+            } else {
+                1u64.checked_shl((resolution & 0x7F) as u32)
+                    .unwrap_or(u64::MAX)
+            };
+        }
+
+        offset += 4 + ((length + 3) & !3);
+    }
+
+    Ok(interface)
+}
+
+fn timestamp_from_units(units: u64, units_per_second: u64) -> Duration {
+    let nanos = units as u128 * 1_000_000_000 / units_per_second.max(1) as u128;
+    Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+}
+
+fn read_u16(data: &[u8], big_endian: bool) -> u16 {
+    let bytes = [data[0], data[1]];
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+fn read_u32(data: &[u8], big_endian: bool) -> u32 {
+    let bytes = [data[0], data[1], data[2], data[3]];
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn read_exact_or_eof<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<bool, Error> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(Error::new(
+                    ErrorKind::CaptureError,
+                    "Capture ends in the middle of a record",
+                ))
+            }
+            Ok(n) => read += n,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        }
+    }
+
+    Ok(true)
+}
+
+fn skip<R: Read>(reader: &mut R, length: usize) -> Result<(), Error> {
+    let skipped = std::io::copy(&mut reader.take(length as u64), &mut std::io::sink())?;
+    if skipped < length as u64 {
+        return Err(Error::new(
+            ErrorKind::CaptureError,
+            "Capture ends in the middle of a block",
+        ));
+    }
+
+    Ok(())
+}
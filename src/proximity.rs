@@ -1,30 +1,436 @@
+use std::{fmt::Display, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
-use crate::messages::ProximityPairMessage;
+use crate::{messages::ProximityPairMessage, Error, ErrorKind};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum ProximityDeviceModel {
     Unknown = 0xFFFF,
     Airpods = 0x0220,
     AirpodsPro = 0x0E20,
     AirpodsMax = 0x0A20,
+    AirpodsMaxUsbC = 0x1F20,
     AirpodsGen2 = 0x0F20,
     AirpodsGen3 = 0x1320,
+    AirpodsGen4 = 0x1920,
+    AirpodsGen4Anc = 0x1B20,
     AirpodsProGen2 = 0x1420,
+    AirpodsProGen2UsbC = 0x2420,
     PowerBeats = 0x0320,
+    PowerBeats4 = 0x0D20,
     PowerBeatsPro = 0x0B20,
+    PowerBeatsPro2 = 0x1D20,
     BeatsSoloPro = 0x0C20,
     BeatsStudioBuds = 0x1120,
     BeatsFlex = 0x1020,
     BeatsX = 0x0520,
     BeatsSolo3 = 0x0620,
+    BeatsSolo4 = 0x2520,
+    BeatsSoloBuds = 0x2620,
     BeatsStudio3 = 0x0920,
     BeatsStudioPro = 0x1720,
     BeatsFitPro = 0x1220,
     BeatsStudioBudsPlus = 0x1620,
+    BeatsPill = 0x2920,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ModelCapabilities {
+    /// Comes with a charging case that reports its own battery and lid state.
+    pub has_case: bool,
+    /// A single unit, such as over-ear headphones or a speaker, rather than two earbuds.
+    pub single_unit: bool,
+    pub noise_cancellation: bool,
+    pub spatial_audio: bool,
+    pub case_speaker: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ModelInfo {
+    pub model: ProximityDeviceModel,
+    pub name: &'static str,
+    pub capabilities: ModelCapabilities,
+    /// The color codes the model is sold in and their names.
+    pub colors: &'static [(u8, &'static str)],
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+const EARBUDS: ModelCapabilities = ModelCapabilities {
+    has_case: true,
+    single_unit: false,
+    noise_cancellation: false,
+    spatial_audio: false,
+    case_speaker: false,
+};
+
+const SINGLE_UNIT: ModelCapabilities = ModelCapabilities {
+    has_case: false,
+    single_unit: true,
+    noise_cancellation: false,
+    spatial_audio: false,
+    case_speaker: false,
+};
+
+// Each model numbers its colors independently, so the same code names different colors across
+// models. Codes are as reported by devices seen on air; models whose codes haven't been
+// collected yet have an empty palette.
+const AIRPODS_COLORS: &[(u8, &str)] = &[(0x00, "White")];
+
+const AIRPODS_MAX_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x02, "Red"),
+    (0x03, "Blue"),
+    (0x0F, "Black"),
+    (0x11, "Light Green"),
+];
+
+const POWERBEATS3_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x0B, "Gray and Blue"),
+    (0x0C, "Gray and Red"),
+    (0x0D, "Gray and Green"),
+    (0x12, "Red"),
+    (0x13, "Swamp Green"),
+    (0x14, "Gray"),
+    (0x15, "Deep Blue"),
+    (0x17, "Black with Gold Logo"),
+];
+
+const POWERBEATS_PRO_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x02, "Yellowish Green"),
+    (0x03, "Blue"),
+    (0x04, "Black"),
+    (0x05, "Pink"),
+    (0x06, "Red"),
+    (0x0B, "Gray"),
+    (0x0D, "Sky Blue"),
+];
+
+const BEATS_FLEX_COLORS: &[(u8, &str)] = &[(0x00, "White"), (0x01, "Black")];
+
+const BEATSX_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x02, "Blue"),
+    (0x05, "Gray"),
+    (0x1D, "Pink"),
+    (0x25, "Black and Red"),
+];
+
+const BEATS_SOLO3_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x06, "Gray"),
+    (0x07, "Gold and White"),
+    (0x08, "Rose Gold"),
+    (0x09, "Black"),
+    (0x0E, "Violet and White"),
+    (0x0F, "Bright Red"),
+    (0x12, "Dark Red"),
+    (0x13, "Swamp Green"),
+    (0x14, "Dark Gray"),
+    (0x15, "Dark Blue"),
+    (0x1D, "Rose Gold"),
+    (0x20, "Blue and Green"),
+    (0x21, "Purple and Orange"),
+    (0x22, "Deep Blue and Light Blue"),
+    (0x23, "Magenta and Light Fuchsia"),
+    (0x25, "Black and Red"),
+    (0x2A, "Gray"),
+    (0x2E, "Pinkish White"),
+    (0x3D, "Red and Blue"),
+    (0x3E, "Yellow and Blue"),
+    (0x3F, "White and Red"),
+    (0x40, "Purple and White"),
+    (0x5B, "Gold"),
+    (0x5C, "Silver"),
+];
+
+const BEATS_SOLO_PRO_COLORS: &[(u8, &str)] = &[(0x00, "White"), (0x01, "Black")];
+
+const BEATS_STUDIO3_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x02, "Red"),
+    (0x03, "Blue"),
+    (0x18, "Shadow Gray"),
+    (0x19, "Desert Sand"),
+    (0x25, "Black and Red"),
+    (0x26, "Midnight Black"),
+    (0x27, "Desert Sand"),
+    (0x28, "Gray"),
+    (0x29, "Clear Blue and Gold"),
+    (0x42, "Forest Camo"),
+    (0x43, "White Camo"),
+];
+
+const BEATS_STUDIO_PRO_COLORS: &[(u8, &str)] = &[(0x00, "White"), (0x01, "Black")];
+
+const BEATS_STUDIO_BUDS_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x02, "Red"),
+    (0x03, "Blue"),
+    (0x04, "Pink"),
+    (0x06, "Silver"),
+];
+
+const BEATS_STUDIO_BUDS_PLUS_COLORS: &[(u8, &str)] = &[
+    (0x00, "Black"),
+    (0x01, "Black and Gold"),
+    (0x02, "Silver"),
+    (0x03, "Tan"),
+    (0x04, "Transparent"),
+];
+
+const BEATS_FIT_PRO_COLORS: &[(u8, &str)] = &[
+    (0x00, "White"),
+    (0x01, "Black"),
+    (0x02, "Pink"),
+    (0x03, "Gray and White"),
+    (0x04, "Full Pink"),
+    (0x05, "Neon Green"),
+    (0x06, "Night Blue"),
+    (0x07, "Light Pink"),
+    (0x08, "Brown"),
+    (0x09, "Dark Brown"),
+];
+
+const UNKNOWN_COLORS: &[(u8, &str)] = &[];
+
+pub const MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        model: ProximityDeviceModel::Airpods,
+        name: "AirPods (1st generation)",
+        colors: AIRPODS_COLORS,
+        capabilities: EARBUDS,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsGen2,
+        name: "AirPods (2nd generation)",
+        colors: AIRPODS_COLORS,
+        capabilities: EARBUDS,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsGen3,
+        name: "AirPods (3rd generation)",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsGen4,
+        name: "AirPods 4",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsGen4Anc,
+        name: "AirPods 4 with Active Noise Cancellation",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            case_speaker: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsPro,
+        name: "AirPods Pro",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsProGen2,
+        name: "AirPods Pro (2nd generation)",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            case_speaker: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsProGen2UsbC,
+        name: "AirPods Pro (2nd generation, USB-C)",
+        colors: AIRPODS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            case_speaker: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsMax,
+        name: "AirPods Max",
+        colors: AIRPODS_MAX_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::AirpodsMaxUsbC,
+        name: "AirPods Max (USB-C)",
+        colors: UNKNOWN_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::PowerBeats,
+        name: "Powerbeats3",
+        colors: POWERBEATS3_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::PowerBeats4,
+        name: "Powerbeats",
+        colors: UNKNOWN_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::PowerBeatsPro,
+        name: "Powerbeats Pro",
+        colors: POWERBEATS_PRO_COLORS,
+        capabilities: EARBUDS,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::PowerBeatsPro2,
+        name: "Powerbeats Pro 2",
+        colors: UNKNOWN_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsFlex,
+        name: "Beats Flex",
+        colors: BEATS_FLEX_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsX,
+        name: "BeatsX",
+        colors: BEATSX_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsSolo3,
+        name: "Beats Solo3",
+        colors: BEATS_SOLO3_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsSolo4,
+        name: "Beats Solo 4",
+        colors: UNKNOWN_COLORS,
+        capabilities: ModelCapabilities {
+            spatial_audio: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsSoloPro,
+        name: "Beats Solo Pro",
+        colors: BEATS_SOLO_PRO_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsSoloBuds,
+        name: "Beats Solo Buds",
+        colors: UNKNOWN_COLORS,
+        // The case has no battery of its own, so there is no case level or lid to report
+        capabilities: ModelCapabilities {
+            has_case: false,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsStudio3,
+        name: "Beats Studio3",
+        colors: BEATS_STUDIO3_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsStudioPro,
+        name: "Beats Studio Pro",
+        colors: BEATS_STUDIO_PRO_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..SINGLE_UNIT
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsStudioBuds,
+        name: "Beats Studio Buds",
+        colors: BEATS_STUDIO_BUDS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsStudioBudsPlus,
+        name: "Beats Studio Buds +",
+        colors: BEATS_STUDIO_BUDS_PLUS_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsFitPro,
+        name: "Beats Fit Pro",
+        colors: BEATS_FIT_PRO_COLORS,
+        capabilities: ModelCapabilities {
+            noise_cancellation: true,
+            spatial_audio: true,
+            ..EARBUDS
+        },
+    },
+    ModelInfo {
+        model: ProximityDeviceModel::BeatsPill,
+        name: "Beats Pill",
+        colors: UNKNOWN_COLORS,
+        capabilities: SINGLE_UNIT,
+    },
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum ProximityDeviceColor {
     Unknown = 0xFF,
     White = 0x00,
@@ -42,7 +448,28 @@ pub enum ProximityDeviceColor {
     Yellow = 0x0C,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// Byte 2 of the message, distinguishing a paired device's status advertisement from one
+/// advertising to pair.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PairingMode {
+    Paired = 0x01,
+    Pairing = 0x07,
+    Unknown = 0xFF,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Pod {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
 pub enum Status {
     Off = 0x00,
     InEar = 0x01,
@@ -51,57 +478,115 @@ pub enum Status {
     Unknown = 0xFF,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct BatteryState {
-    pub case: u32,
-    pub left: u32,
-    pub right: u32,
+    pub case: Option<u32>,
+    pub left: Option<u32>,
+    pub right: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ChargingState {
     pub case: bool,
     pub left: bool,
     pub right: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DeviceState {
     pub case_open: bool,
     pub left: Status,
     pub right: Status,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-pub struct ProximityDevice {
-    pub model: ProximityDeviceModel,
-    pub color: ProximityDeviceColor,
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Earbuds {
     pub battery: BatteryState,
     pub charging_state: ChargingState,
     pub state: DeviceState,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Headphones {
+    pub battery: Option<u32>,
+    pub charging: bool,
+    pub on_head: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceShape {
+    Earbuds(Earbuds),
+    Headphones(Headphones),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProximityDevice {
+    pub model: ProximityDeviceModel,
+    /// The color code in the palette shared by early models. Use `color_name` to name the
+    /// device's color, as later models number their colors independently.
+    pub color: ProximityDeviceColor,
+    /// The raw color code, which `color_name` resolves in the model's own palette.
+    pub device_color: u8,
+    pub pairing_mode: PairingMode,
+    /// The pod sending the advertisement (the UTP bit), whose status is reported first.
+    pub primary_pod: Pod,
+    /// The top two bits of the device status. Their values aren't fully understood, but they
+    /// change with the device playing audio.
+    pub audio_source: u8,
+    pub shape: DeviceShape,
+    pub lid_open_count: u8,
+}
+
 impl From<u16> for ProximityDeviceModel {
     fn from(val: u16) -> Self {
+        MODELS
+            .iter()
+            .find(|info| info.model as u16 == val)
+            .map(|info| info.model)
+            .unwrap_or(ProximityDeviceModel::Unknown)
+    }
+}
+
+impl ProximityDeviceModel {
+    pub fn info(&self) -> Option<&'static ModelInfo> {
+        MODELS.iter().find(|info| info.model == *self)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.info().map(|info| info.name).unwrap_or("Unknown")
+    }
+
+    /// The model's capabilities. Unknown models are assumed to be earbuds with a case so
+    /// nothing is hidden.
+    pub fn capabilities(&self) -> ModelCapabilities {
+        self.info().map(|info| info.capabilities).unwrap_or(EARBUDS)
+    }
+
+    /// The name of a color code for this model, as the same code can mean different colors
+    /// across models. `None` for codes outside the model's palette and for unknown models.
+    pub fn color_name(&self, color: u8) -> Option<&'static str> {
+        self.info()?
+            .colors
+            .iter()
+            .find(|(code, _)| *code == color)
+            .map(|(_, name)| *name)
+    }
+}
+
+impl From<u8> for PairingMode {
+    fn from(val: u8) -> Self {
         match val {
-            0x0220 => ProximityDeviceModel::Airpods,
-            0x0E20 => ProximityDeviceModel::AirpodsPro,
-            0x0A20 => ProximityDeviceModel::AirpodsMax,
-            0x0F20 => ProximityDeviceModel::AirpodsGen2,
-            0x1320 => ProximityDeviceModel::AirpodsGen3,
-            0x1420 => ProximityDeviceModel::AirpodsProGen2,
-            0x0320 => ProximityDeviceModel::PowerBeats,
-            0x0B20 => ProximityDeviceModel::PowerBeatsPro,
-            0x0C20 => ProximityDeviceModel::BeatsSoloPro,
-            0x1120 => ProximityDeviceModel::BeatsStudioBuds,
-            0x1020 => ProximityDeviceModel::BeatsFlex,
-            0x0520 => ProximityDeviceModel::BeatsX,
-            0x0620 => ProximityDeviceModel::BeatsSolo3,
-            0x0920 => ProximityDeviceModel::BeatsStudio3,
-            0x1720 => ProximityDeviceModel::BeatsStudioPro,
-            0x1220 => ProximityDeviceModel::BeatsFitPro,
-            0x1620 => ProximityDeviceModel::BeatsStudioBudsPlus,
-            _ => ProximityDeviceModel::Unknown,
+            0x01 => PairingMode::Paired,
+            0x07 => PairingMode::Pairing,
+            _ => PairingMode::Unknown,
         }
     }
 }
@@ -163,8 +648,8 @@ impl From<u8> for DeviceState {
     }
 }
 
-impl From<ProximityPairMessage> for ProximityDevice {
-    fn from(val: ProximityPairMessage) -> Self {
+impl Earbuds {
+    fn from_message(val: &ProximityPairMessage, has_case: bool) -> Earbuds {
         let flipped = (val.device_status >> 4) & 0x02 == 0;
 
         let case_battery = val.battery2 & 0x0F;
@@ -180,34 +665,288 @@ impl From<ProximityPairMessage> for ProximityDevice {
             val.battery1 >> 4
         };
 
+        // Models without a case still send a case nibble, usually 0xF
+        let case_battery = battery_level(case_battery).filter(|_| has_case);
+        let left_battery = battery_level(left_battery);
+        let right_battery = battery_level(right_battery);
+
+        // Charging bits are meaningless for components that aren't reporting a level
         let charge_status = val.battery2 >> 4;
-        let case_charging = (charge_status & 0x04) != 0;
-        let left_charging = if flipped {
-            (charge_status & 0x02) != 0
-        } else {
-            (charge_status & 0x01) != 0
-        };
+        let case_charging = case_battery.is_some() && (charge_status & 0x04) != 0;
+        let left_charging = left_battery.is_some()
+            && if flipped {
+                (charge_status & 0x02) != 0
+            } else {
+                (charge_status & 0x01) != 0
+            };
 
-        let right_charging = if flipped {
-            (charge_status & 0x01) != 0
-        } else {
-            (charge_status & 0x02) != 0
-        };
+        let right_charging = right_battery.is_some()
+            && if flipped {
+                (charge_status & 0x01) != 0
+            } else {
+                (charge_status & 0x02) != 0
+            };
 
-        ProximityDevice {
-            model: ProximityDeviceModel::from(val.device_model),
-            color: ProximityDeviceColor::from(val.device_color),
-            state: DeviceState::from(val.device_status),
+        let mut state = DeviceState::from(val.device_status);
+        state.case_open &= has_case;
+
+        Earbuds {
             battery: BatteryState {
-                case: case_battery as u32 * 10,
-                left: left_battery as u32 * 10,
-                right: right_battery as u32 * 10,
+                case: case_battery,
+                left: left_battery,
+                right: right_battery,
             },
             charging_state: ChargingState {
                 case: case_charging,
                 left: left_charging,
                 right: right_charging,
             },
+            state,
         }
     }
 }
+
+impl From<&ProximityPairMessage> for Headphones {
+    fn from(val: &ProximityPairMessage) -> Self {
+        // There are no pods to flip, the one battery is sent in either pod nibble with the
+        // other left unavailable
+        let charge_status = val.battery2 >> 4;
+        let (battery, charging) = match (
+            battery_level(val.battery1 & 0x0F),
+            battery_level(val.battery1 >> 4),
+        ) {
+            (Some(level), _) => (Some(level), (charge_status & 0x01) != 0),
+            (None, Some(level)) => (Some(level), (charge_status & 0x02) != 0),
+            (None, None) => (None, false),
+        };
+
+        let on_head = [
+            (val.device_status >> 1) & 0x03,
+            (val.device_status >> 3) & 0x03,
+        ]
+        .into_iter()
+        .any(|status| Status::from(status) == Status::InEar);
+
+        Headphones {
+            battery,
+            charging,
+            on_head,
+        }
+    }
+}
+
+impl ProximityDevice {
+    pub fn earbuds(&self) -> Option<&Earbuds> {
+        match &self.shape {
+            DeviceShape::Earbuds(earbuds) => Some(earbuds),
+            DeviceShape::Headphones(_) => None,
+        }
+    }
+
+    /// The model-specific name of the device's color.
+    pub fn color_name(&self) -> Option<&'static str> {
+        self.model.color_name(self.device_color)
+    }
+
+    pub fn headphones(&self) -> Option<&Headphones> {
+        match &self.shape {
+            DeviceShape::Headphones(headphones) => Some(headphones),
+            DeviceShape::Earbuds(_) => None,
+        }
+    }
+}
+
+impl From<ProximityPairMessage> for ProximityDevice {
+    fn from(val: ProximityPairMessage) -> Self {
+        let model = ProximityDeviceModel::from(val.device_model);
+        let capabilities = model.capabilities();
+
+        let shape = if capabilities.single_unit {
+            DeviceShape::Headphones(Headphones::from(&val))
+        } else {
+            DeviceShape::Earbuds(Earbuds::from_message(&val, capabilities.has_case))
+        };
+
+        ProximityDevice {
+            model,
+            color: ProximityDeviceColor::from(val.device_color),
+            device_color: val.device_color,
+            pairing_mode: PairingMode::from(val.prefix),
+            primary_pod: if (val.device_status >> 5) & 0x01 != 0 {
+                Pod::Left
+            } else {
+                Pod::Right
+            },
+            audio_source: val.device_status >> 6,
+            shape,
+            lid_open_count: val.lid_open_count,
+        }
+    }
+}
+
+impl Display for ProximityDeviceModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ProximityDeviceModel {
+    type Err = crate::Error;
+
+    /// Parses a known model by its serialized name, such as `airpods_pro`, or its display name,
+    /// such as `AirPods Pro`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        MODELS
+            .iter()
+            .map(|info| info.model)
+            .find(|model| {
+                model.name().to_lowercase() == name
+                    || serde_json::to_value(model)
+                        .ok()
+                        .as_ref()
+                        .and_then(|v| v.as_str())
+                        == Some(name.as_str())
+            })
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::DecodeError,
+                    format!("Unknown model: {}", s).as_str(),
+                )
+            })
+    }
+}
+
+impl Display for ProximityDeviceColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProximityDeviceColor::White => "White",
+                ProximityDeviceColor::Black => "Black",
+                ProximityDeviceColor::Red => "Red",
+                ProximityDeviceColor::Blue => "Blue",
+                ProximityDeviceColor::Pink => "Pink",
+                ProximityDeviceColor::Gray => "Gray",
+                ProximityDeviceColor::Silver => "Silver",
+                ProximityDeviceColor::Gold => "Gold",
+                ProximityDeviceColor::RoseGold => "Rose Gold",
+                ProximityDeviceColor::SpaceGray => "Space Gray",
+                ProximityDeviceColor::DarkBlue => "Dark Blue",
+                ProximityDeviceColor::LightBlue => "Light Blue",
+                ProximityDeviceColor::Yellow => "Yellow",
+                ProximityDeviceColor::Unknown => "Unknown color",
+            }
+        )
+    }
+}
+
+impl Display for PairingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PairingMode::Paired => "paired",
+                PairingMode::Pairing => "pairing",
+                PairingMode::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl Display for Pod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Pod::Left => "left",
+                Pod::Right => "right",
+            }
+        )
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Status::Off => "off",
+                Status::InEar => "in ear",
+                Status::InCase => "in case",
+                Status::AirplaneMode => "airplane mode",
+                Status::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+/// A battery level followed by a lightning bolt while charging, or `-` without a level.
+fn format_battery(level: Option<u32>, charging: bool) -> String {
+    match (level, charging) {
+        (Some(level), true) => format!("{}% \u{26A1}", level),
+        (Some(level), false) => format!("{}%", level),
+        (None, _) => "-".to_string(),
+    }
+}
+
+impl Display for ProximityDevice {
+    /// For example `AirPods Pro (2nd generation), White — L 80% ⚡, R 70%, Case 50%, lid open`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}, {} \u{2014} ",
+            self.model,
+            self.color_name().unwrap_or("Unknown color")
+        )?;
+
+        match &self.shape {
+            DeviceShape::Earbuds(earbuds) => {
+                write!(
+                    f,
+                    "L {}, R {}",
+                    format_battery(earbuds.battery.left, earbuds.charging_state.left),
+                    format_battery(earbuds.battery.right, earbuds.charging_state.right),
+                )?;
+
+                if self.model.capabilities().has_case {
+                    write!(
+                        f,
+                        ", Case {}, lid {}",
+                        format_battery(earbuds.battery.case, earbuds.charging_state.case),
+                        if earbuds.state.case_open {
+                            "open"
+                        } else {
+                            "closed"
+                        }
+                    )?;
+                }
+
+                Ok(())
+            }
+            DeviceShape::Headphones(headphones) => write!(
+                f,
+                "{}, {}",
+                format_battery(headphones.battery, headphones.charging),
+                if headphones.on_head {
+                    "on head"
+                } else {
+                    "off head"
+                }
+            ),
+        }
+    }
+}
+
+/// Converts a battery nibble to a percentage. 0x0 to 0xA are levels in 10% steps, 0xF means the
+/// component isn't connected and anything else is undefined.
+fn battery_level(nibble: u8) -> Option<u32> {
+    match nibble {
+        0x00..=0x0A => Some(nibble as u32 * 10),
+        _ => None,
+    }
+}
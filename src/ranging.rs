@@ -0,0 +1,240 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::proximity::ProximityDeviceModel;
+
+/// The iBeacon convention for the RSSI measured 1m from a phone-class transmitter.
+pub const DEFAULT_TX_POWER: i8 = -59;
+
+/// Converts an RSSI and the RSSI expected at 1m into a distance in meters.
+pub trait PathLossModel {
+    fn distance(&self, rssi: f64, tx_power: f64) -> f64;
+}
+
+/// The log-distance path loss model. An exponent of 2.0 is free space, indoor environments
+/// are usually between 2.0 and 4.0.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LogDistance {
+    pub exponent: f64,
+}
+
+/// A curve fitted to measured RSSI/distance pairs, `a * (rssi / tx_power)^b + c`, as used by
+/// the Android Beacon Library.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct FittedCurve {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Zone {
+    Immediate,
+    Near,
+    Far,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RangingConfig {
+    /// The weight of each new RSSI sample in the exponential moving average, from 0.0 to 1.0.
+    pub smoothing: f64,
+    /// The furthest distance in meters classified as `Zone::Immediate`.
+    pub immediate: f64,
+    /// The furthest distance in meters classified as `Zone::Near`.
+    pub near: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RangeEstimate {
+    /// The filtered RSSI.
+    pub rssi: f64,
+    pub tx_power: i8,
+    pub distance: f64,
+    pub zone: Zone,
+}
+
+/// The RSSI at 1m for each model, for messages that don't carry a measured power. The default
+/// calibration has no model entries, as there are no published reference values, so every model
+/// uses `default_tx_power` until values measured with `with_model` are added.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Calibration {
+    pub default_tx_power: i8,
+    pub models: Vec<(ProximityDeviceModel, i8)>,
+}
+
+/// Smooths RSSI samples from one device and estimates its distance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RangeEstimator<M: PathLossModel = LogDistance> {
+    model: M,
+    config: RangingConfig,
+    rssi: Option<f64>,
+    tx_power: i8,
+}
+
+impl Default for LogDistance {
+    fn default() -> Self {
+        LogDistance { exponent: 2.0 }
+    }
+}
+
+impl PathLossModel for LogDistance {
+    fn distance(&self, rssi: f64, tx_power: f64) -> f64 {
+        10f64.powf((tx_power - rssi) / (10.0 * self.exponent))
+    }
+}
+
+impl Default for FittedCurve {
+    fn default() -> Self {
+        // Fitted for a Nexus 4, the library's reference device
+        FittedCurve {
+            a: 0.42093,
+            b: 6.9476,
+            c: 0.54992,
+        }
+    }
+}
+
+impl PathLossModel for FittedCurve {
+    fn distance(&self, rssi: f64, tx_power: f64) -> f64 {
+        let ratio = rssi / tx_power;
+        if ratio < 1.0 {
+            ratio.powi(10)
+        } else {
+            self.a * ratio.powf(self.b) + self.c
+        }
+    }
+}
+
+impl Default for RangingConfig {
+    fn default() -> Self {
+        RangingConfig {
+            smoothing: 0.25,
+            immediate: 0.5,
+            near: 4.0,
+        }
+    }
+}
+
+impl RangingConfig {
+    pub fn zone(&self, distance: f64) -> Zone {
+        if distance <= self.immediate {
+            Zone::Immediate
+        } else if distance <= self.near {
+            Zone::Near
+        } else {
+            Zone::Far
+        }
+    }
+}
+
+impl Display for Zone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Zone::Immediate => "immediate",
+                Zone::Near => "near",
+                Zone::Far => "far",
+            }
+        )
+    }
+}
+
+impl Display for RangeEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}m ({}), RSSI {:.0} dBm",
+            self.distance, self.zone, self.rssi
+        )
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            default_tx_power: DEFAULT_TX_POWER,
+            models: Vec::new(),
+        }
+    }
+}
+
+impl Calibration {
+    /// Sets the RSSI at 1m measured for `model`.
+    pub fn with_model(mut self, model: ProximityDeviceModel, tx_power: i8) -> Calibration {
+        self.models.retain(|(existing, _)| *existing != model);
+        self.models.push((model, tx_power));
+        self
+    }
+
+    pub fn tx_power(&self, model: ProximityDeviceModel) -> i8 {
+        self.models
+            .iter()
+            .find(|(existing, _)| *existing == model)
+            .map(|(_, tx_power)| *tx_power)
+            .unwrap_or(self.default_tx_power)
+    }
+}
+
+impl Default for RangeEstimator {
+    fn default() -> Self {
+        RangeEstimator::new(LogDistance::default(), RangingConfig::default())
+    }
+}
+
+impl<M: PathLossModel> RangeEstimator<M> {
+    pub fn new(model: M, config: RangingConfig) -> RangeEstimator<M> {
+        RangeEstimator {
+            model,
+            config,
+            rssi: None,
+            tx_power: DEFAULT_TX_POWER,
+        }
+    }
+
+    pub fn config(&self) -> &RangingConfig {
+        &self.config
+    }
+
+    /// Adds an RSSI sample along with the RSSI at 1m for the transmitter, from the message or
+    /// a `Calibration`.
+    pub fn push(&mut self, rssi: i8, tx_power: i8) -> RangeEstimate {
+        let rssi = match self.rssi {
+            Some(previous) => previous + self.config.smoothing * (rssi as f64 - previous),
+            None => rssi as f64,
+        };
+        self.rssi = Some(rssi);
+        self.tx_power = tx_power;
+
+        self.range(rssi)
+    }
+
+    /// The estimate from the samples so far, `None` before the first sample.
+    pub fn estimate(&self) -> Option<RangeEstimate> {
+        self.rssi.map(|rssi| self.range(rssi))
+    }
+
+    pub fn reset(&mut self) {
+        self.rssi = None;
+    }
+
+    fn range(&self, rssi: f64) -> RangeEstimate {
+        let distance = self.model.distance(rssi, self.tx_power as f64);
+
+        RangeEstimate {
+            rssi,
+            tx_power: self.tx_power,
+            distance,
+            zone: self.config.zone(distance),
+        }
+    }
+}
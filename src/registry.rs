@@ -0,0 +1,153 @@
+//! Maps opcodes to the decoders `Message::decode` dispatches to. Applications can register
+//! decoders for message types the crate doesn't know yet, or replace the built-in ones, and
+//! install the result for every decode in the process.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, OnceLock, PoisonError, RwLock},
+};
+
+use crate::{
+    messages::{
+        AirPrintMessage, AirplayTargetMessage, HandoffMessage, Message, MessageOpcode,
+        NearbyInfoMessage, ProximityPairMessage,
+    },
+    Error, ErrorKind,
+};
+
+/// Decodes messages with one opcode. Implemented for closures taking the message bytes, which
+/// start with the opcode and length.
+pub trait Decoder: Send + Sync {
+    fn decode(&self, data: &[u8]) -> Result<Message, Error>;
+}
+
+impl<F> Decoder for F
+where
+    F: Fn(&[u8]) -> Result<Message, Error> + Send + Sync,
+{
+    fn decode(&self, data: &[u8]) -> Result<Message, Error> {
+        self(data)
+    }
+}
+
+#[derive(Clone)]
+pub struct Registry {
+    decoders: BTreeMap<u8, Arc<dyn Decoder>>,
+}
+
+impl Default for Registry {
+    /// A registry with the decoders for every message type the crate knows.
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        registry.register(MessageOpcode::Airprint.into(), |data: &[u8]| {
+            Ok(Message::Airprint(AirPrintMessage::decode(data)?))
+        });
+        registry.register(MessageOpcode::ProximityPairing.into(), |data: &[u8]| {
+            Ok(Message::ProximityPairing(ProximityPairMessage::decode(
+                data,
+            )?))
+        });
+        registry.register(MessageOpcode::AirplayTarget.into(), |data: &[u8]| {
+            Ok(Message::AirplayTarget(AirplayTargetMessage::decode(data)?))
+        });
+        registry.register(MessageOpcode::Handoff.into(), |data: &[u8]| {
+            Ok(Message::Handoff(HandoffMessage::decode(data)?))
+        });
+        registry.register(MessageOpcode::NearbyInfo.into(), |data: &[u8]| {
+            Ok(Message::NearbyInfo(NearbyInfoMessage::decode(data)?))
+        });
+        registry
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Registry")
+            .field("opcodes", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+fn opcode(data: &[u8]) -> Result<u8, Error> {
+    data.first()
+        .copied()
+        .ok_or_else(|| Error::new(ErrorKind::DecodeError, "Empty message"))
+}
+
+fn unknown_opcode(opcode: u8) -> Error {
+    Error::new(
+        ErrorKind::DecodeError,
+        format!("Unknown opcode: {:02X?}", opcode).as_str(),
+    )
+}
+
+fn global() -> &'static RwLock<Registry> {
+    static GLOBAL: OnceLock<RwLock<Registry>> = OnceLock::new();
+    GLOBAL.get_or_init(|| RwLock::new(Registry::default()))
+}
+
+impl Registry {
+    /// A registry without any decoders, not even the built-in ones.
+    pub fn empty() -> Registry {
+        Registry {
+            decoders: BTreeMap::new(),
+        }
+    }
+
+    /// A copy of the registry `Message::decode` currently dispatches through.
+    pub fn current() -> Registry {
+        global()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Makes `Message::decode`, and everything decoding through it, dispatch through this
+    /// registry.
+    pub fn install(self) {
+        *global().write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// Decodes messages with `opcode` using `decoder`, replacing any decoder already registered.
+    pub fn register<D: Decoder + 'static>(&mut self, opcode: u8, decoder: D) {
+        self.decoders.insert(opcode, Arc::new(decoder));
+    }
+
+    /// Stops decoding messages with `opcode`, returning whether a decoder was registered.
+    pub fn unregister(&mut self, opcode: u8) -> bool {
+        self.decoders.remove(&opcode).is_some()
+    }
+
+    pub fn decoder(&self, opcode: u8) -> Option<&dyn Decoder> {
+        self.decoders.get(&opcode).map(|decoder| decoder.as_ref())
+    }
+
+    pub fn opcodes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.decoders.keys().copied()
+    }
+
+    pub fn decode(&self, data: &[u8]) -> Result<Message, Error> {
+        let opcode = opcode(data)?;
+        match self.decoder(opcode) {
+            Some(decoder) => decoder.decode(data),
+            None => Err(unknown_opcode(opcode)),
+        }
+    }
+
+    /// Decodes `data` with the installed registry. The decoder is called after releasing the
+    /// lock, so it may itself decode, register or install.
+    pub(crate) fn decode_global(data: &[u8]) -> Result<Message, Error> {
+        let opcode = opcode(data)?;
+        let decoder = global()
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .decoders
+            .get(&opcode)
+            .cloned();
+
+        match decoder {
+            Some(decoder) => decoder.decode(data),
+            None => Err(unknown_opcode(opcode)),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! The JSON representation of the library's types is stable within a schema version: struct
+//! fields and enum variants are snake_case, enums are externally tagged and raw byte fields are
+//! lowercase hex strings. Durations are `{"secs": .., "nanos": ..}` objects.
+
+/// Bumped whenever a serialized type changes incompatibly.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schemas for the top-level types, by file name. Written to `schema/` by
+/// `cargo run --example schema --features schema`.
+#[cfg(feature = "schema")]
+pub fn schemas() -> Vec<(&'static str, schemars::Schema)> {
+    use schemars::schema_for;
+
+    use crate::{
+        advertisement::Advertisement, airplay::AirPlayReceiver, airprint::Printer,
+        battery::BatteryEstimate, dissect::Field, dnssd::ServiceRecords, events::ProximityEvent,
+        jsonl::JsonlRecord, linker::LogicalDevice, messages::Message, proximity::ProximityDevice,
+        ranging::RangeEstimate, tracker::TrackerEvent,
+    };
+
+    vec![
+        ("message", schema_for!(Message)),
+        ("advertisement", schema_for!(Advertisement)),
+        ("jsonl_record", schema_for!(JsonlRecord)),
+        ("proximity_device", schema_for!(ProximityDevice)),
+        ("proximity_event", schema_for!(ProximityEvent)),
+        ("printer", schema_for!(Printer)),
+        ("service_records", schema_for!(ServiceRecords)),
+        ("airplay_receiver", schema_for!(AirPlayReceiver)),
+        ("tracker_event", schema_for!(TrackerEvent)),
+        ("logical_device", schema_for!(LogicalDevice)),
+        ("battery_estimate", schema_for!(BatteryEstimate)),
+        ("range_estimate", schema_for!(RangeEstimate)),
+        ("dissection", schema_for!(Field)),
+    ]
+}
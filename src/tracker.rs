@@ -0,0 +1,213 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    advertisement::{Address, Advertisement},
+    battery::BatteryHistory,
+    messages::Message,
+    proximity::ProximityDevice,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TrackerConfig {
+    /// How long a device can go unheard before it is reported as lost.
+    pub timeout: Duration,
+    /// How many RSSI samples are kept per device.
+    pub rssi_history: usize,
+    /// How long battery levels are kept per device.
+    pub battery_history: Duration,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RssiSample {
+    pub timestamp: Duration,
+    pub rssi: i8,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TrackedDevice {
+    pub address: Address,
+    pub first_seen: Duration,
+    pub last_seen: Duration,
+    pub message_count: usize,
+    pub proximity: Option<ProximityDevice>,
+    pub battery: BatteryHistory,
+    pub rssi_history: VecDeque<RssiSample>,
+    /// The most recent message of each opcode seen from the device.
+    pub latest_messages: BTreeMap<u8, Message>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TrackerEvent {
+    Appeared(TrackedDevice),
+    Updated(TrackedDevice),
+    Lost(TrackedDevice),
+}
+
+/// Aggregates decoded messages per advertiser address and reports devices appearing, changing
+/// and going quiet.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DeviceTracker {
+    config: TrackerConfig,
+    devices: HashMap<Address, TrackedDevice>,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        TrackerConfig {
+            timeout: Duration::from_secs(30),
+            rssi_history: 32,
+            battery_history: Duration::from_secs(4 * 60 * 60),
+        }
+    }
+}
+
+impl TrackedDevice {
+    fn new(address: Address, timestamp: Duration, config: &TrackerConfig) -> TrackedDevice {
+        TrackedDevice {
+            address,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            message_count: 0,
+            proximity: None,
+            battery: BatteryHistory::new(config.battery_history),
+            rssi_history: VecDeque::new(),
+            latest_messages: BTreeMap::new(),
+        }
+    }
+
+    /// The opcodes of every message type seen from the device.
+    pub fn message_types(&self) -> impl Iterator<Item = u8> + '_ {
+        self.latest_messages.keys().copied()
+    }
+
+    /// The mean of the RSSI samples currently held.
+    pub fn average_rssi(&self) -> Option<f64> {
+        if self.rssi_history.is_empty() {
+            return None;
+        }
+
+        let total: f64 = self
+            .rssi_history
+            .iter()
+            .map(|sample| sample.rssi as f64)
+            .sum();
+        Some(total / self.rssi_history.len() as f64)
+    }
+}
+
+impl DeviceTracker {
+    pub fn new(config: TrackerConfig) -> DeviceTracker {
+        DeviceTracker {
+            config,
+            devices: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    pub fn get(&self, address: &Address) -> Option<&TrackedDevice> {
+        self.devices.get(address)
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &TrackedDevice> {
+        self.devices.values()
+    }
+
+    /// Records a message from `address`. Devices that have timed out as of `timestamp` are
+    /// reported as lost first. An `Updated` event is only emitted when the message differs from
+    /// the last one with the same opcode.
+    pub fn ingest(
+        &mut self,
+        timestamp: Duration,
+        address: Address,
+        rssi: Option<i8>,
+        message: Message,
+    ) -> Vec<TrackerEvent> {
+        let mut events = self.expire(timestamp);
+
+        let appeared = !self.devices.contains_key(&address);
+        let config = &self.config;
+        let device = self
+            .devices
+            .entry(address)
+            .or_insert_with(|| TrackedDevice::new(address, timestamp, config));
+
+        device.first_seen = device.first_seen.min(timestamp);
+        device.last_seen = device.last_seen.max(timestamp);
+        device.message_count += 1;
+
+        if let Some(rssi) = rssi {
+            device
+                .rssi_history
+                .push_back(RssiSample { timestamp, rssi });
+            while device.rssi_history.len() > self.config.rssi_history {
+                device.rssi_history.pop_front();
+            }
+        }
+
+        if let Message::ProximityPairing(message) = &message {
+            let proximity = ProximityDevice::from(message.clone());
+            device.battery.push(timestamp, &proximity);
+            device.proximity = Some(proximity);
+        }
+
+        let opcode = message.header().opcode;
+        let changed = match device.latest_messages.get(&opcode) {
+            Some(previous) => previous.encode() != message.encode(),
+            None => true,
+        };
+        device.latest_messages.insert(opcode, message);
+
+        if appeared {
+            events.push(TrackerEvent::Appeared(device.clone()));
+        } else if changed {
+            events.push(TrackerEvent::Updated(device.clone()));
+        }
+
+        events
+    }
+
+    /// Records every message in an advertisement.
+    pub fn ingest_advertisement(&mut self, advertisement: &Advertisement) -> Vec<TrackerEvent> {
+        advertisement
+            .messages()
+            .into_iter()
+            .flat_map(|message| {
+                self.ingest(
+                    advertisement.timestamp,
+                    advertisement.address,
+                    advertisement.rssi,
+                    message,
+                )
+            })
+            .collect()
+    }
+
+    /// Removes devices not heard from within the timeout as of `now`.
+    pub fn expire(&mut self, now: Duration) -> Vec<TrackerEvent> {
+        let timeout = self.config.timeout;
+        let lost: Vec<Address> = self
+            .devices
+            .values()
+            .filter(|device| now.saturating_sub(device.last_seen) > timeout)
+            .map(|device| device.address)
+            .collect();
+
+        lost.iter()
+            .filter_map(|address| self.devices.remove(address))
+            .map(TrackerEvent::Lost)
+            .collect()
+    }
+}
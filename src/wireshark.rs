@@ -0,0 +1,223 @@
+//! Generates a Wireshark Lua dissector for Apple Continuity manufacturer data from the layouts in
+//! [`crate::layout`], so Wireshark splits messages into the same fields the crate decodes.
+
+use std::fmt::Write;
+
+use crate::{
+    advertisement::APPLE_COMPANY_ID,
+    layout::{FieldLayout, MessageLayout, LAYOUTS},
+    messages::MessageOpcode,
+};
+
+/// The Lua protocol name, and the prefix of every field filter such as
+/// `continuity.airprint.port`.
+pub const PROTOCOL: &str = "continuity";
+
+/// Sentence case label for a field name, such as `Device model` for `device_model`.
+fn label(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+fn lua_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The `ProtoField` constructor for a field of its size.
+fn proto_field(field: &FieldLayout) -> &'static str {
+    match field.size {
+        Some(1) => "ProtoField.uint8",
+        Some(2) => "ProtoField.uint16",
+        Some(3) => "ProtoField.uint24",
+        Some(4) => "ProtoField.uint32",
+        _ => "ProtoField.bytes",
+    }
+}
+
+fn declare_field(lua: &mut String, variable: &str, filter: &str, field: &FieldLayout) {
+    let constructor = proto_field(field);
+    let base = if constructor == "ProtoField.bytes" {
+        "base.NONE"
+    } else {
+        "base.HEX_DEC"
+    };
+
+    // Bytes fields take the description in place of a value string and mask
+    let description = match (field.doc.trim(), constructor) {
+        ("", _) => String::new(),
+        (doc, "ProtoField.bytes") => format!(", {}", lua_string(doc)),
+        (doc, _) => format!(", nil, nil, {}", lua_string(doc)),
+    };
+
+    let _ = writeln!(
+        lua,
+        "f.{} = {}(\"{}\", \"{}\", {}{})",
+        variable,
+        constructor,
+        filter,
+        label(field.name),
+        base,
+        description
+    );
+
+    for bits in field.bits {
+        let _ = writeln!(
+            lua,
+            "f.{}_{} = ProtoField.uint8(\"{}.{}\", \"{}\", base.DEC, nil, 0x{:02X})",
+            variable,
+            bits.name,
+            filter,
+            bits.name,
+            label(bits.name),
+            bits.mask
+        );
+    }
+}
+
+fn declare_layout(lua: &mut String, layout: &MessageLayout) {
+    let key = layout.key();
+    for field in layout.fields {
+        declare_field(
+            lua,
+            &format!("{}_{}", key, field.name),
+            &format!("{}.{}.{}", PROTOCOL, key, field.name),
+            field,
+        );
+    }
+}
+
+/// One entry of the `layouts` table: the message name and, for each field, its ProtoField,
+/// its size (0 for the rest of the message) and the ProtoFields of its bits.
+fn layout_entry(lua: &mut String, layout: &MessageLayout) {
+    let key = layout.key();
+    let _ = writeln!(lua, "    [0x{:02X}] = {{", layout.opcode);
+    let _ = writeln!(lua, "        name = \"{}\",", layout.name);
+    let _ = writeln!(lua, "        fields = {{");
+
+    for field in layout.fields {
+        let variable = format!("{}_{}", key, field.name);
+        let bits: Vec<String> = field
+            .bits
+            .iter()
+            .map(|bits| format!("f.{}_{}", variable, bits.name))
+            .collect();
+
+        let _ = writeln!(
+            lua,
+            "            {{ f.{}, {}, {{{}}} }},",
+            variable,
+            field.size.unwrap_or(0),
+            bits.join(", ")
+        );
+    }
+
+    let _ = writeln!(lua, "        }},");
+    let _ = writeln!(lua, "    }},");
+}
+
+/// A Lua dissector for the Apple manufacturer specific data in BLE advertisements. Load it
+/// from Wireshark's plugin directory, or with `wireshark -X lua_script:continuity.lua`.
+pub fn lua_dissector() -> String {
+    let mut lua = String::new();
+
+    let _ = writeln!(
+        lua,
+        "-- Apple Continuity dissector generated by apple-continuity {}. Do not edit; regenerate\n\
+         -- with `continuity wireshark` instead.\n",
+        env!("CARGO_PKG_VERSION")
+    );
+    let _ = writeln!(
+        lua,
+        "local continuity = Proto(\"{}\", \"Apple Continuity\")",
+        PROTOCOL
+    );
+    let _ = writeln!(lua, "local f = continuity.fields\n");
+
+    let _ = writeln!(lua, "local opcodes = {{");
+    for opcode in MessageOpcode::ALL {
+        let _ = writeln!(lua, "    [0x{:02X}] = \"{}\",", opcode as u8, opcode.name());
+    }
+    let _ = writeln!(lua, "}}\n");
+
+    let _ = writeln!(
+        lua,
+        "f.opcode = ProtoField.uint8(\"{0}.opcode\", \"Opcode\", base.HEX, opcodes)\n\
+         f.length = ProtoField.uint8(\"{0}.length\", \"Length\", base.DEC)",
+        PROTOCOL
+    );
+
+    for layout in LAYOUTS {
+        lua.push('\n');
+        declare_layout(&mut lua, layout);
+    }
+
+    let _ = writeln!(lua, "\nlocal layouts = {{");
+    for layout in LAYOUTS {
+        layout_entry(&mut lua, layout);
+    }
+    let _ = writeln!(lua, "}}\n");
+
+    lua.push_str(DISSECTOR);
+    let _ = writeln!(
+        lua,
+        "\nDissectorTable.get(\"btcommon.eir_ad.manufacturer_company_id\"):add(0x{:04X}, continuity)",
+        APPLE_COMPANY_ID
+    );
+
+    lua
+}
+
+// Walks the messages in the manufacturer data, which Wireshark hands over without the company
+// identifier, adding each message's fields in layout order
+const DISSECTOR: &str = r#"function continuity.dissector(buffer, pinfo, tree)
+    local offset = 0
+    local names = {}
+
+    while offset + 2 <= buffer:len() do
+        local opcode = buffer(offset, 1):uint()
+        local size = math.min(buffer(offset + 1, 1):uint() + 2, buffer:len() - offset)
+        local layout = layouts[opcode]
+        local name = opcodes[opcode] or string.format("Unknown (0x%02X)", opcode)
+        local subtree = tree:add(continuity, buffer(offset, size), name)
+
+        subtree:add(f.opcode, buffer(offset, 1))
+        subtree:add(f.length, buffer(offset + 1, 1))
+
+        if layout then
+            local position = offset + 2
+            local finish = offset + size
+
+            for _, field in ipairs(layout.fields) do
+                local width = field[2]
+                if width == 0 then
+                    width = finish - position
+                end
+                if width <= 0 or position + width > finish then
+                    break
+                end
+
+                local item = subtree:add(field[1], buffer(position, width))
+                for _, bits in ipairs(field[3]) do
+                    item:add(bits, buffer(position, 1))
+                end
+                position = position + width
+            end
+
+            if position < finish then
+                subtree:add(buffer(position, finish - position), "Padding")
+            end
+        end
+
+        table.insert(names, name)
+        offset = offset + size
+    end
+
+    pinfo.cols.protocol = "Continuity"
+    pinfo.cols.info = table.concat(names, ", ")
+    return offset
+end
+"#;
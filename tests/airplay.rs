@@ -0,0 +1,82 @@
+use std::net::Ipv4Addr;
+
+use apple_continuity::{
+    airplay::{AirPlayFlags, AirPlayReceiver},
+    messages::{AirplayTargetMessage, Message},
+};
+
+fn receiver(flags: u8, seed: u8) -> AirPlayReceiver {
+    let data = [0x09, 0x06, flags, seed, 0xC0, 0xA8, 0x01, 0x02];
+    match Message::decode(&data).unwrap() {
+        Message::AirplayTarget(message) => AirPlayReceiver::from(message),
+        message => panic!("expected AirPlay Target, got {:?}", message),
+    }
+}
+
+#[test]
+fn decodes_receiver() {
+    let receiver = receiver(0x03, 0x05);
+    assert_eq!(receiver.ipv4_address, Ipv4Addr::new(192, 168, 1, 2));
+    assert_eq!(receiver.seed, 0x05);
+    assert_eq!(
+        receiver.flags,
+        AirPlayFlags {
+            screen_mirroring: true,
+            password_required: true,
+            pin_required: false,
+            access_control: false,
+            raw: 0x03,
+        }
+    );
+    assert_eq!(
+        receiver.to_string(),
+        "192.168.1.2, screen mirroring, password, seed 05"
+    );
+}
+
+#[test]
+fn decodes_flag_bits() {
+    let flags = |value: u8| {
+        let flags = AirPlayFlags::from(value);
+        [
+            flags.screen_mirroring,
+            flags.password_required,
+            flags.pin_required,
+            flags.access_control,
+        ]
+    };
+
+    assert_eq!(flags(0x00), [false; 4]);
+    assert_eq!(flags(0x01), [true, false, false, false]);
+    assert_eq!(flags(0x02), [false, true, false, false]);
+    assert_eq!(flags(0x04), [false, false, true, false]);
+    assert_eq!(flags(0x08), [false, false, false, true]);
+
+    // Unknown bits are kept in the raw byte only
+    let unknown = AirPlayFlags::from(0xF4);
+    assert_eq!(unknown.raw, 0xF4);
+    assert!(unknown.pin_required && !unknown.access_control);
+
+    assert_eq!(AirPlayFlags::from(0x00).to_string(), "no flags");
+    assert_eq!(AirPlayFlags::from(0x0C).to_string(), "PIN, access control");
+}
+
+#[test]
+fn converts_decoded_message_fields() {
+    let message =
+        AirplayTargetMessage::decode(&[0x09, 0x06, 0x08, 0x2A, 0x0A, 0x00, 0x00, 0x07]).unwrap();
+    let receiver = AirPlayReceiver::from(message);
+    assert_eq!(receiver.ipv4_address, Ipv4Addr::new(10, 0, 0, 7));
+    assert!(receiver.flags.access_control);
+    assert_eq!(receiver.seed, 0x2A);
+}
+
+#[test]
+fn detects_config_changes() {
+    let previous = receiver(0x01, 0x05);
+    assert!(!receiver(0x01, 0x05).config_changed(&previous));
+    assert!(receiver(0x01, 0x06).config_changed(&previous));
+
+    // Only the seed announces a change, not the flags
+    assert!(!receiver(0x03, 0x05).config_changed(&previous));
+}
@@ -0,0 +1,195 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use apple_continuity::{
+    advertisement::{manufacturer_data, split_messages},
+    airprint::{AddressType, Printer, ResourceCatalog, ResourceType, SecurityType},
+    dnssd::{ServiceRecords, TxtRecord},
+    hex,
+    messages::{AirPrintMessage, Message},
+};
+
+// Synthesized advertising data, not captured from real printers: flags followed by the Apple
+// manufacturer data, laid out as printers send it
+const SYNTHESIZED_IPV4: &str = "0201061BFF4C0003161000000277C0A80117000000000000000000000000C5";
+const SYNTHESIZED_IPV6: &str = "0201061BFF4C0003162100010277FE80000000000000021132FFFE5A8C01BE";
+const SYNTHESIZED_PRINTER_ID: &str =
+    "0201061BFF4C0003161412341F910A00002A000000000000000000000000C8";
+
+fn decode_advertisement(hex: &str) -> AirPrintMessage {
+    let data = hex::decode(hex).unwrap();
+    let body = manufacturer_data(&data).expect("no Apple manufacturer data");
+    let message = split_messages(body).next().expect("no message");
+
+    match Message::decode(message).unwrap() {
+        Message::Airprint(message) => message,
+        message => panic!("expected AirPrint, got {:?}", message),
+    }
+}
+
+#[test]
+fn decodes_ipv4_printer() {
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV4));
+
+    assert_eq!(printer.address_type, AddressType::IPv4);
+    assert_eq!(printer.resource_type, ResourceType::ResourcePath);
+    assert_eq!(printer.security_type, SecurityType::None);
+    assert_eq!(printer.ipv4_address, Some(Ipv4Addr::new(192, 168, 1, 23)));
+    assert_eq!(printer.ipv6_address, None);
+    assert_eq!(printer.port, 631);
+    assert_eq!(printer.resource_path.as_deref(), Some("/ipp/print"));
+    assert_eq!(printer.tx_power(), -59);
+}
+
+#[test]
+fn decodes_ipv6_printer() {
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV6));
+
+    assert_eq!(printer.address_type, AddressType::IPv6);
+    assert_eq!(printer.security_type, SecurityType::TLS);
+    assert_eq!(printer.ipv4_address, None);
+    assert_eq!(
+        printer.ipv6_address,
+        Some("fe80::211:32ff:fe5a:8c01".parse::<Ipv6Addr>().unwrap())
+    );
+    assert_eq!(printer.resource_path.as_deref(), Some("/ipp/printer"));
+    assert_eq!(printer.tx_power(), -66);
+}
+
+#[test]
+fn decodes_printer_id() {
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_PRINTER_ID));
+
+    assert_eq!(printer.resource_type, ResourceType::PrinterId);
+    assert_eq!(printer.printer_id, Some(0x1234));
+    assert_eq!(printer.resource_path, None);
+    assert_eq!(printer.ipv4_address, Some(Ipv4Addr::new(10, 0, 0, 42)));
+    assert_eq!(printer.port, 8081);
+}
+
+#[test]
+fn builds_addresses_and_uris() {
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV4));
+    assert_eq!(
+        printer.ip_addr(),
+        Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 23)))
+    );
+    assert_eq!(
+        printer.socket_addr(),
+        Some("192.168.1.23:631".parse::<SocketAddr>().unwrap())
+    );
+    assert_eq!(
+        printer.uri().as_deref(),
+        Some("ipp://192.168.1.23/ipp/print")
+    );
+
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV6));
+    assert_eq!(
+        printer.socket_addr(),
+        Some(
+            "[fe80::211:32ff:fe5a:8c01]:631"
+                .parse::<SocketAddr>()
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        printer.uri().as_deref(),
+        Some("ipps://[fe80::211:32ff:fe5a:8c01]/ipp/printer")
+    );
+
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_PRINTER_ID));
+    assert_eq!(
+        printer.uri().as_deref(),
+        Some("ipp://10.0.0.42:8081/ipp/print")
+    );
+}
+
+#[test]
+fn leaves_unknown_resource_paths_without_uri() {
+    let mut message = decode_advertisement(SYNTHESIZED_IPV4);
+    message.rp_index_value = 9;
+
+    let printer = Printer::from(message.clone());
+    assert_eq!(printer.resource_path, None);
+    assert_eq!(printer.ipp_path(), None);
+    assert_eq!(printer.uri(), None);
+    assert!(ServiceRecords::from_printer(&printer, "Office Printer").is_none());
+    assert!(printer
+        .to_string()
+        .starts_with("192.168.1.23:631, unknown resource path"));
+
+    // A reserved resource type doesn't fall back to the default path either
+    message.rp_index_value = 0;
+    message.connection_info = 0x18;
+    let printer = Printer::from(message);
+    assert_eq!(printer.resource_type, ResourceType::Unknown);
+    assert_eq!(printer.uri(), None);
+
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_PRINTER_ID));
+    assert_eq!(printer.ipp_path(), Some("/ipp/print"));
+}
+
+#[test]
+fn round_trips_advertisements() {
+    for advertisement in [SYNTHESIZED_IPV4, SYNTHESIZED_IPV6, SYNTHESIZED_PRINTER_ID] {
+        let data = hex::decode(advertisement).unwrap();
+        let body = manufacturer_data(&data).unwrap();
+        assert_eq!(decode_advertisement(advertisement).encode(), body);
+    }
+}
+
+#[test]
+fn rejects_short_message() {
+    let data = hex::decode("03101000000277C0A80117000000000000").unwrap();
+    assert!(AirPrintMessage::decode(&data).is_err());
+}
+
+#[test]
+fn builds_dns_sd_records() {
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV6));
+    let records = ServiceRecords::from_printer(&printer, "Office Printer").unwrap();
+
+    assert_eq!(records.full_name(), "Office Printer._ipps._tcp.local.");
+    assert_eq!(records.srv.port, 631);
+    assert_eq!(
+        records.srv.target,
+        "airprint-fe80--211-32ff-fe5a-8c01.local."
+    );
+    assert_eq!(
+        records.address,
+        "fe80::211:32ff:fe5a:8c01".parse::<IpAddr>().unwrap()
+    );
+    assert!(records.txt.contains(&TxtRecord::new("rp", "ipp/printer")));
+    assert!(records.txt.contains(&TxtRecord::new("TLS", "1.2")));
+
+    let printer = Printer::from(decode_advertisement(SYNTHESIZED_IPV4));
+    let records = ServiceRecords::from_printer(&printer, "Lobby").unwrap();
+    assert_eq!(records.service_name(), "_ipp._tcp.local.");
+    assert!(!records.txt.iter().any(|txt| txt.key == "TLS"));
+}
+
+#[test]
+fn resolves_catalog_entries() {
+    let mut catalog = ResourceCatalog::from_json(
+        r#"{"printers": {"4660": {"vendor": "Brother", "resource_path": "/ipp/print"}}}"#,
+    )
+    .unwrap();
+    assert_eq!(catalog.resource_path(0), None);
+
+    catalog.extend(ResourceCatalog::default());
+    catalog.register_path(7, "/ipp/queue");
+
+    let printer = Printer::from_message(decode_advertisement(SYNTHESIZED_PRINTER_ID), &catalog);
+    assert_eq!(printer.printer_id, Some(0x1234));
+    assert_eq!(printer.vendor.as_deref(), Some("Brother"));
+    assert_eq!(printer.resource_path.as_deref(), Some("/ipp/print"));
+
+    let mut message = decode_advertisement(SYNTHESIZED_IPV4);
+    message.rp_index_value = 7;
+    assert_eq!(Printer::from(message.clone()).resource_path, None);
+    assert_eq!(
+        Printer::from_message(message, &catalog)
+            .resource_path
+            .as_deref(),
+        Some("/ipp/queue")
+    );
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use apple_continuity::{
+    battery::{BatteryEstimate, BatteryHistory},
+    events::Component,
+    messages::ProximityPairMessage,
+    proximity::{DeviceShape, ProximityDevice},
+};
+use common::{seconds, AIRPODS_PRO};
+
+fn device(left: u32, charging: bool) -> ProximityDevice {
+    let mut device = ProximityDevice::from(ProximityPairMessage::decode(&AIRPODS_PRO).unwrap());
+    match &mut device.shape {
+        DeviceShape::Earbuds(earbuds) => {
+            earbuds.battery.left = Some(left);
+            earbuds.charging_state.left = charging;
+        }
+        shape => panic!("expected earbuds, got {:?}", shape),
+    }
+    device
+}
+
+/// A history with a left pod sample every minute, at `level(second)` for each second up to
+/// and including `until`.
+fn history(until: u64, charging: bool, level: impl Fn(u64) -> u32) -> BatteryHistory {
+    let mut history = BatteryHistory::new(seconds(4 * 60 * 60));
+    for second in (0..=until).step_by(60) {
+        history.push(seconds(second), &device(level(second), charging));
+    }
+    history
+}
+
+fn left_estimate(history: &BatteryHistory) -> BatteryEstimate {
+    history.estimate(Component::Left).unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn estimates_discharge() {
+    // Steps from 90 to 80 at 630 s and from 80 to 70 at 1230 s: 10% every 10 minutes
+    let history = history(1800, false, |second| match second {
+        0..=600 => 90,
+        601..=1200 => 80,
+        _ => 70,
+    });
+
+    let estimate = left_estimate(&history);
+    assert_eq!(estimate.level, 70);
+    assert_close(estimate.rate.unwrap(), -60.0);
+    assert_close(estimate.smoothed_level, 70.5);
+    assert_close(estimate.time_to_empty.unwrap().as_secs_f64(), 4230.0);
+    assert_eq!(estimate.time_to_full, None);
+}
+
+#[test]
+fn estimates_charge() {
+    let history = history(1800, true, |second| match second {
+        0..=600 => 50,
+        601..=1200 => 60,
+        _ => 70,
+    });
+
+    let estimate = left_estimate(&history);
+    assert_close(estimate.rate.unwrap(), 60.0);
+    assert_close(estimate.smoothed_level, 79.5);
+    assert_close(estimate.time_to_full.unwrap().as_secs_f64(), 1230.0);
+    assert_eq!(estimate.time_to_empty, None);
+}
+
+#[test]
+fn bounds_rate_by_time_since_last_step() {
+    // Without another step in the 1770 s since the last one, the level has fallen by less
+    // than 10% in that time
+    let history = history(3000, false, |second| match second {
+        0..=600 => 90,
+        601..=1200 => 80,
+        _ => 70,
+    });
+
+    let estimate = left_estimate(&history);
+    assert_close(estimate.rate.unwrap(), -10.0 / 1770.0 * 3600.0);
+    assert_close(estimate.smoothed_level, 70.0);
+}
+
+#[test]
+fn keeps_flat_series_without_rate() {
+    let estimate = left_estimate(&history(3600, false, |_| 50));
+    assert_eq!(estimate.level, 50);
+    assert_eq!(estimate.rate, None);
+    assert_close(estimate.smoothed_level, 55.0);
+    assert_eq!(estimate.time_to_empty, None);
+    assert_eq!(estimate.time_to_full, None);
+}
+
+#[test]
+fn needs_two_steps_for_rate() {
+    let estimate = left_estimate(&history(
+        1200,
+        false,
+        |second| {
+            if second <= 600 {
+                50
+            } else {
+                40
+            }
+        },
+    ));
+    assert_eq!(estimate.level, 40);
+    assert_eq!(estimate.rate, None);
+    assert_close(estimate.smoothed_level, 45.0);
+
+    // Steps in opposite directions don't make a rate either
+    let estimate = left_estimate(&history(1800, false, |second| match second {
+        0..=600 => 50,
+        601..=1200 => 40,
+        _ => 50,
+    }));
+    assert_eq!(estimate.rate, None);
+}
+
+#[test]
+fn restarts_after_charging_changes() {
+    let mut history = history(1800, false, |second| match second {
+        0..=600 => 90,
+        601..=1200 => 80,
+        _ => 70,
+    });
+    history.push(seconds(1860), &device(70, true));
+
+    let estimate = left_estimate(&history);
+    assert_eq!(estimate.rate, None);
+    assert_eq!(BatteryHistory::default().estimate(Component::Left), None);
+}
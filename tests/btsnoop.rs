@@ -0,0 +1,160 @@
+use std::time::Duration;
+
+use apple_continuity::{
+    advertisement::{Address, PduType},
+    btsnoop::{decode_event, BtsnoopReader, DATALINK_HCI_UART, DATALINK_HCI_UNENCAPSULATED},
+    messages::Message,
+    ErrorKind,
+};
+
+const BTSNOOP_EPOCH_OFFSET: u64 = 0x00DC_DDB3_0F2F_8000;
+
+const NEARBY_INFO: [u8; 14] = [
+    0x02, 0x01, 0x06, 0x0A, 0xFF, 0x4C, 0x00, 0x10, 0x05, 0x03, 0x18, 0x11, 0x22, 0x33,
+];
+
+// An LE Advertising Report with two reports: an ADV_IND from a random address with RSSI -60
+// and a SCAN_RSP from a public address without RSSI
+fn advertising_report() -> Vec<u8> {
+    let mut parameters = vec![0x02, 0x02, 0x00, 0x04, 0x01, 0x00];
+    parameters.extend_from_slice(&[0x01, 0x00, 0x00, 0xEE, 0xFF, 0xC0]);
+    parameters.extend_from_slice(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x4C]);
+    parameters.extend_from_slice(&[NEARBY_INFO.len() as u8, 0x03]);
+    parameters.extend_from_slice(&NEARBY_INFO);
+    parameters.extend_from_slice(&[0x02, 0x09, 0x41]);
+    parameters.extend_from_slice(&[-60i8 as u8, 127]);
+
+    event(&parameters)
+}
+
+// An LE Extended Advertising Report carrying a legacy ADV_NONCONN_IND with RSSI -72
+fn extended_advertising_report() -> Vec<u8> {
+    let mut parameters = vec![0x0D, 0x01, 0x10, 0x00, 0x01];
+    parameters.extend_from_slice(&[0x02, 0x00, 0x00, 0xEE, 0xFF, 0xC0]);
+    // Primary and secondary PHY, SID, TX power, RSSI and periodic advertising interval
+    parameters.extend_from_slice(&[0x01, 0x00, 0xFF, 0x7F, -72i8 as u8, 0x00, 0x00]);
+    // No direct address
+    parameters.extend_from_slice(&[0x00; 7]);
+    parameters.push(NEARBY_INFO.len() as u8);
+    parameters.extend_from_slice(&NEARBY_INFO);
+
+    event(&parameters)
+}
+
+fn event(parameters: &[u8]) -> Vec<u8> {
+    let mut event = vec![0x3E, parameters.len() as u8];
+    event.extend_from_slice(parameters);
+    event
+}
+
+fn capture(datalink: u32, records: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut capture = b"btsnoop\0".to_vec();
+    capture.extend_from_slice(&1u32.to_be_bytes());
+    capture.extend_from_slice(&datalink.to_be_bytes());
+
+    for (i, (flags, data)) in records.iter().enumerate() {
+        let timestamp = BTSNOOP_EPOCH_OFFSET + 1_000_000 * (i as u64 + 1);
+        capture.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        capture.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        capture.extend_from_slice(&flags.to_be_bytes());
+        capture.extend_from_slice(&0u32.to_be_bytes());
+        capture.extend_from_slice(&timestamp.to_be_bytes());
+        capture.extend_from_slice(data);
+    }
+
+    capture
+}
+
+fn address(text: &str) -> Address {
+    text.parse().unwrap()
+}
+
+#[test]
+fn reads_advertising_reports() {
+    let capture = capture(
+        DATALINK_HCI_UNENCAPSULATED,
+        &[
+            // A sent command, which is skipped
+            (0x02, vec![0x0C, 0x20, 0x02, 0x01, 0x00]),
+            (0x03, advertising_report()),
+            (0x03, extended_advertising_report()),
+        ],
+    );
+
+    let advertisements: Vec<_> = BtsnoopReader::new(capture.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(advertisements.len(), 3);
+
+    let first = &advertisements[0];
+    assert_eq!(first.timestamp, Duration::from_secs(2));
+    assert_eq!(first.address, address("C0:FF:EE:00:00:01"));
+    assert!(first.random_address);
+    assert_eq!(first.pdu_type, PduType::AdvInd);
+    assert_eq!(first.rssi, Some(-60));
+    assert!(matches!(
+        first.messages().as_slice(),
+        [Message::NearbyInfo(_)]
+    ));
+
+    let second = &advertisements[1];
+    assert_eq!(second.address, address("4C:00:00:00:00:03"));
+    assert!(!second.random_address);
+    assert_eq!(second.pdu_type, PduType::ScanRsp);
+    assert_eq!(second.rssi, None);
+    assert_eq!(second.data, [0x02, 0x09, 0x41]);
+
+    let third = &advertisements[2];
+    assert_eq!(third.timestamp, Duration::from_secs(3));
+    assert_eq!(third.address, address("C0:FF:EE:00:00:02"));
+    assert_eq!(third.pdu_type, PduType::AdvNonconnInd);
+    assert_eq!(third.rssi, Some(-72));
+    assert_eq!(third.data, NEARBY_INFO);
+}
+
+#[test]
+fn reads_uart_datalink() {
+    let mut report = vec![0x04];
+    report.extend(advertising_report());
+
+    let capture = capture(
+        DATALINK_HCI_UART,
+        &[
+            (0x00, vec![0x01, 0x0C, 0x20, 0x02, 0x01, 0x00]),
+            (0x01, report),
+        ],
+    );
+    let advertisements: Vec<_> = BtsnoopReader::new(capture.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(advertisements.len(), 2);
+}
+
+#[test]
+fn ignores_malformed_events() {
+    assert!(decode_event(&[0x3E, 0x00, 0x02, 0x01], Duration::ZERO).is_empty());
+    assert!(decode_event(&[0x3E, 0x02, 0x02, 0x05], Duration::ZERO).is_empty());
+    assert!(decode_event(&[0x3E, 0x02, 0x0D, 0x01], Duration::ZERO).is_empty());
+    assert!(decode_event(&[0x0E, 0x04, 0x01, 0x0C, 0x20, 0x00], Duration::ZERO).is_empty());
+
+    let mut truncated = advertising_report();
+    truncated.truncate(20);
+    assert!(decode_event(&truncated, Duration::ZERO).is_empty());
+}
+
+#[test]
+fn rejects_oversized_records() {
+    let mut capture = capture(DATALINK_HCI_UNENCAPSULATED, &[]);
+    capture.extend_from_slice(&u32::MAX.to_be_bytes());
+    capture.extend_from_slice(&u32::MAX.to_be_bytes());
+    capture.extend_from_slice(&[0; 16]);
+
+    let error = BtsnoopReader::new(capture.as_slice())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::CaptureError));
+}
@@ -0,0 +1,134 @@
+use std::{path::PathBuf, process::Command};
+
+use apple_continuity::capture::{self, CaptureFormat};
+
+const PHDR_PCAP: &[u8] = include_bytes!("fixtures/le_ll_phdr.pcap");
+const LE_LL_PCAPNG: &[u8] = include_bytes!("fixtures/le_ll.pcapng");
+const JSONL: &str = "{\"address\": \"C0:FF:EE:00:00:01\", \"data\": \"0AFF4C0010050318112233\"}\n";
+// Proximity Pairing advertisements from AirPods Pro and AirPods Max
+const PROXIMITY_JSONL: &str = "\
+    {\"address\": \"C0:FF:EE:00:00:01\", \"data\": \"1EFF4C000719010E2055983511000000000000000000000000000000000000\"}\n\
+    {\"address\": \"C0:FF:EE:00:00:02\", \"data\": \"1EFF4C000719010A2055983511000000000000000000000000000000000000\"}\n";
+
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("continuity-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn detects_formats() {
+    assert_eq!(CaptureFormat::detect(PHDR_PCAP), CaptureFormat::Pcap);
+    assert_eq!(CaptureFormat::detect(LE_LL_PCAPNG), CaptureFormat::Pcap);
+    assert_eq!(
+        CaptureFormat::detect(&[0xA1, 0xB2, 0xC3, 0xD4]),
+        CaptureFormat::Pcap
+    );
+    assert_eq!(
+        CaptureFormat::detect(&[0x4D, 0x3C, 0xB2, 0xA1]),
+        CaptureFormat::Pcap
+    );
+    assert_eq!(
+        CaptureFormat::detect(b"btsnoop\0\0\0\0\x01"),
+        CaptureFormat::Btsnoop
+    );
+    assert_eq!(
+        CaptureFormat::detect(JSONL.as_bytes()),
+        CaptureFormat::Jsonl
+    );
+    assert_eq!(CaptureFormat::detect(&[]), CaptureFormat::Jsonl);
+}
+
+#[test]
+fn parses_format_names() {
+    for (name, format) in [
+        ("pcap", CaptureFormat::Pcap),
+        ("PCAPNG", CaptureFormat::Pcap),
+        ("btsnoop", CaptureFormat::Btsnoop),
+        ("jsonl", CaptureFormat::Jsonl),
+        ("json", CaptureFormat::Jsonl),
+    ] {
+        assert_eq!(name.parse::<CaptureFormat>().unwrap(), format);
+    }
+
+    assert!("csv".parse::<CaptureFormat>().is_err());
+    assert_eq!(CaptureFormat::Btsnoop.to_string(), "btsnoop");
+}
+
+#[test]
+fn opens_detected_and_given_formats() {
+    let pcapng = temp_file("detect.pcapng", LE_LL_PCAPNG);
+    let jsonl = temp_file("detect.jsonl", JSONL.as_bytes());
+
+    assert_eq!(capture::open(&pcapng, None).unwrap().count(), 4);
+    assert_eq!(capture::open(&jsonl, None).unwrap().count(), 1);
+    assert_eq!(
+        capture::open(&jsonl, Some(CaptureFormat::Jsonl))
+            .unwrap()
+            .count(),
+        1
+    );
+
+    // A given format overrides detection
+    assert!(capture::open(&jsonl, Some(CaptureFormat::Pcap)).is_err());
+    assert!(capture::open(&pcapng, Some(CaptureFormat::Btsnoop)).is_err());
+
+    std::fs::remove_file(pcapng).unwrap();
+    std::fs::remove_file(jsonl).unwrap();
+}
+
+#[test]
+fn replays_with_format_option() {
+    let jsonl = temp_file("replay.jsonl", JSONL.as_bytes());
+    let replay = |format: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_continuity"))
+            .arg("replay")
+            .args(format)
+            .arg("--quiet")
+            .arg(&jsonl)
+            .output()
+            .unwrap()
+    };
+
+    for format in [&[][..], &["--format", "jsonl"]] {
+        let output = replay(format);
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("C0:FF:EE:00:00:01"));
+    }
+
+    let output = replay(&["--format", "pcap"]);
+    assert!(!output.status.success());
+
+    let output = replay(&["--format", "csv"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown capture format: csv"));
+
+    std::fs::remove_file(jsonl).unwrap();
+}
+
+#[test]
+fn replays_with_model_filter() {
+    let jsonl = temp_file("models.jsonl", PROXIMITY_JSONL.as_bytes());
+    let replay = |model: &str| {
+        Command::new(env!("CARGO_BIN_EXE_continuity"))
+            .args(["replay", "--quiet", "--model", model])
+            .arg(&jsonl)
+            .output()
+            .unwrap()
+    };
+
+    for model in ["airpods_pro", "AirPods Pro"] {
+        let output = replay(model);
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("C0:FF:EE:00:00:01"));
+        assert!(stdout.contains(" AirPods Pro "));
+        assert!(!stdout.contains("C0:FF:EE:00:00:02"));
+    }
+
+    let output = replay("AirpodsPr");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown model: AirpodsPr"));
+
+    std::fs::remove_file(jsonl).unwrap();
+}
@@ -0,0 +1,61 @@
+use apple_continuity::{
+    airprint::{Printer, ResourceCatalog},
+    messages::{AirPrintMessage, Message},
+};
+
+// The AirPrint message of SYNTHESIZED_PRINTER_ID in tests/airprint.rs
+const PRINTER_ID: [u8; 24] = [
+    0x03, 0x16, 0x14, 0x12, 0x34, 0x1F, 0x91, 0x0A, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC8,
+];
+
+// Restores the default catalog when the test ends, even if it fails
+struct InstalledCatalog;
+
+impl InstalledCatalog {
+    fn install(catalog: ResourceCatalog) -> InstalledCatalog {
+        catalog.install();
+        InstalledCatalog
+    }
+}
+
+impl Drop for InstalledCatalog {
+    fn drop(&mut self) {
+        ResourceCatalog::default().install();
+    }
+}
+
+fn decode() -> AirPrintMessage {
+    match Message::decode(&PRINTER_ID).unwrap() {
+        Message::Airprint(message) => message,
+        message => panic!("expected AirPrint, got {:?}", message),
+    }
+}
+
+#[test]
+fn resolves_printers_with_installed_catalog() {
+    assert_eq!(ResourceCatalog::current(), ResourceCatalog::default());
+    assert_eq!(Printer::from(decode()).vendor, None);
+
+    let mut catalog = ResourceCatalog::current();
+    catalog.extend(
+        ResourceCatalog::from_json(
+            r#"{"printers": {"4660": {"vendor": "Brother", "resource_path": "/ipp/print"}}}"#,
+        )
+        .unwrap(),
+    );
+    let _installed = InstalledCatalog::install(catalog);
+
+    let printer = Printer::from(decode());
+    assert_eq!(printer.vendor.as_deref(), Some("Brother"));
+    assert_eq!(
+        printer.uri().as_deref(),
+        Some("ipp://10.0.0.42:8081/ipp/print")
+    );
+
+    let dissection = Message::dissect(&PRINTER_ID).unwrap();
+    assert!(dissection
+        .children
+        .iter()
+        .any(|field| field.interpreted.as_deref() == Some("printer ID 4660 (Brother)")));
+}
@@ -0,0 +1,101 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde_json::Value;
+
+fn decode(input: &str) -> Vec<Value> {
+    decode_with(&[], input)
+}
+
+fn decode_with(args: &[&str], input: &str) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_continuity"))
+        .args(["decode", "--json"])
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn decodes_every_line_form() {
+    // The same Nearby Info message as a bare message, manufacturer data, advertising data
+    // led by Flags and advertising data with the manufacturer data first
+    let values = decode(
+        "10050318112233\n\
+         4C0010050318112233\n\
+         # comment\n\
+         0201060AFF4C0010050318112233\n\
+         0AFF4C00100503181122330201 06\n",
+    );
+
+    let lines: Vec<_> = values.iter().map(|value| value["line"].clone()).collect();
+    assert_eq!(lines, [1, 2, 4, 5]);
+    for value in &values {
+        assert_eq!(value["message"]["nearby_info"]["auth_tag"], 0x112233);
+    }
+}
+
+#[test]
+fn decodes_flags_prefixed_airprint() {
+    let values = decode("0201061BFF4C0003161000000277C0A80117000000000000000000000000C5\n");
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0]["uri"], "ipp://192.168.1.23/ipp/print");
+}
+
+#[test]
+fn reports_invalid_lines() {
+    let values = decode("zz\n0201\n");
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0]["error"], "DecodeError Not a hex string");
+    assert_eq!(values[1]["line"], 2);
+    assert!(values[1]["error"].is_string());
+}
+
+#[test]
+fn reports_non_ascii_and_odd_length_lines() {
+    let values = decode("aé1\n10050318112233\nabc\n0x10:05-03 18 11 22 33\n");
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0]["error"], "DecodeError Not a hex string");
+    assert_eq!(values[1]["message"]["nearby_info"]["auth_tag"], 0x112233);
+    assert_eq!(
+        values[2]["error"],
+        "DecodeError Hex string has an odd number of digits"
+    );
+    assert_eq!(values[3]["message"]["nearby_info"]["auth_tag"], 0x112233);
+}
+
+#[test]
+fn resolves_printers_with_catalog_file() {
+    let path = std::env::temp_dir().join(format!("continuity-catalog-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"printers": {"4660": {"vendor": "Brother", "resource_path": "/ipp/print"}}}"#,
+    )
+    .unwrap();
+
+    let printer_id = "0201061BFF4C0003161412341F910A00002A000000000000000000000000C8\n";
+    let without = decode(printer_id);
+    let with = decode_with(&["--catalog", path.to_str().unwrap()], printer_id);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(without[0]["printer"]["vendor"].is_null());
+    assert_eq!(with[0]["printer"]["vendor"], "Brother");
+    assert_eq!(with[0]["uri"], "ipp://10.0.0.42:8081/ipp/print");
+}
@@ -0,0 +1,31 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use apple_continuity::advertisement::Address;
+
+// AirPods Pro with both pods in the open case, L 90%, R 80% and the case at 50%
+pub const AIRPODS_PRO: [u8; 27] = [
+    0x07, 0x19, 0x01, 0x0E, 0x20, 0x55, 0x98, 0x35, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// `AIRPODS_PRO` with another model, color and lid open count.
+pub fn proximity_pairing(device_model: u16, device_color: u8, lid_open_count: u8) -> [u8; 27] {
+    let mut data = AIRPODS_PRO;
+    data[3..5].copy_from_slice(&device_model.to_be_bytes());
+    data[8] = lid_open_count;
+    data[9] = device_color;
+    data
+}
+
+/// A resolvable private address, the kind Apple devices rotate.
+pub fn address(last: u8) -> Address {
+    Address([0x7A, 0x11, 0x22, 0x33, 0x44, last])
+}
+
+pub fn seconds(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
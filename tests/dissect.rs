@@ -0,0 +1,147 @@
+mod common;
+
+use apple_continuity::{dissect::Field, hex, messages::Message};
+use common::AIRPODS_PRO;
+
+// The AirPrint message of SYNTHESIZED_IPV6 in tests/airprint.rs: IPv6, resource path, TLS
+const AIRPRINT_IPV6: &str = "03162100010277FE80000000000000021132FFFE5A8C01BE";
+
+fn child<'a>(field: &'a Field, name: &str) -> &'a Field {
+    field
+        .children
+        .iter()
+        .find(|child| child.name == name)
+        .unwrap_or_else(|| panic!("no {} field in {}", name, field.name))
+}
+
+/// The name, range, mask, value and interpretation of each bitfield of `field`.
+fn bits(field: &Field) -> Vec<(&str, usize, u8, u64, &str)> {
+    field
+        .children
+        .iter()
+        .map(|bits| {
+            assert_eq!(bits.range, field.range);
+            (
+                bits.name.as_str(),
+                bits.range.start,
+                bits.mask.unwrap(),
+                bits.value.unwrap(),
+                bits.interpreted.as_deref().unwrap_or(""),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn dissects_device_status() {
+    // 0x55: audio source 1, right primary pod, both pods in the case and the lid open
+    let dissection = Message::dissect(&AIRPODS_PRO).unwrap();
+    let device_status = child(&dissection, "device_status");
+    assert_eq!(device_status.range, 5..6);
+    assert_eq!(device_status.mask, None);
+    assert_eq!(
+        bits(device_status),
+        [
+            ("audio_source", 5, 0xC0, 1, ""),
+            ("primary_pod", 5, 0x20, 0, "right"),
+            ("secondary_pod_status", 5, 0x18, 2, "in case"),
+            ("primary_pod_status", 5, 0x06, 2, "in case"),
+            ("case_open", 5, 0x01, 1, "yes"),
+        ]
+    );
+
+    // 0x2B: left primary pod, both pods in ear
+    let mut data = AIRPODS_PRO;
+    data[5] = 0x2B;
+    let dissection = Message::dissect(&data).unwrap();
+    assert_eq!(
+        bits(child(&dissection, "device_status")),
+        [
+            ("audio_source", 5, 0xC0, 0, ""),
+            ("primary_pod", 5, 0x20, 1, "left"),
+            ("secondary_pod_status", 5, 0x18, 1, "in ear"),
+            ("primary_pod_status", 5, 0x06, 1, "in ear"),
+            ("case_open", 5, 0x01, 1, "yes"),
+        ]
+    );
+}
+
+#[test]
+fn dissects_battery_levels_and_charging() {
+    let dissection = Message::dissect(&AIRPODS_PRO).unwrap();
+    assert_eq!(
+        bits(child(&dissection, "battery1")),
+        [
+            ("secondary_pod_battery", 6, 0xF0, 9, "90%"),
+            ("primary_pod_battery", 6, 0x0F, 8, "80%"),
+        ]
+    );
+
+    // 0x35: both pods charging, the case at 50% and not charging
+    let battery2 = child(&dissection, "battery2");
+    assert_eq!(battery2.range, 7..8);
+    assert_eq!(
+        bits(battery2),
+        [
+            ("unknown", 7, 0x80, 0, ""),
+            ("case_charging", 7, 0x40, 0, "no"),
+            ("secondary_pod_charging", 7, 0x20, 1, "yes"),
+            ("primary_pod_charging", 7, 0x10, 1, "yes"),
+            ("case_battery", 7, 0x0F, 5, "50%"),
+        ]
+    );
+
+    // 0x5F: the case charging while disconnected, which the dissection shows as sent
+    let mut data = AIRPODS_PRO;
+    data[7] = 0x5F;
+    let dissection = Message::dissect(&data).unwrap();
+    assert_eq!(
+        bits(child(&dissection, "battery2")),
+        [
+            ("unknown", 7, 0x80, 0, ""),
+            ("case_charging", 7, 0x40, 1, "yes"),
+            ("secondary_pod_charging", 7, 0x20, 0, "no"),
+            ("primary_pod_charging", 7, 0x10, 1, "yes"),
+            ("case_battery", 7, 0x0F, 0xF, "not connected"),
+        ]
+    );
+}
+
+#[test]
+fn dissects_airprint_connection_info() {
+    let data = hex::decode(AIRPRINT_IPV6).unwrap();
+    let dissection = Message::dissect(&data).unwrap();
+    let connection_info = child(&dissection, "connection_info");
+    assert_eq!(connection_info.range, 2..3);
+    assert_eq!(connection_info.value, Some(0x21));
+    assert_eq!(
+        bits(connection_info),
+        [
+            ("address_type", 2, 0xF0, 2, "IPv6"),
+            ("resource_type", 2, 0x0C, 0, "resource path"),
+            ("security_type", 2, 0x03, 1, "TLS"),
+        ]
+    );
+
+    // An IPv4 printer ID without security
+    let mut data = data;
+    data[2] = 0x14;
+    let dissection = Message::dissect(&data).unwrap();
+    assert_eq!(
+        bits(child(&dissection, "connection_info")),
+        [
+            ("address_type", 2, 0xF0, 1, "IPv4"),
+            ("resource_type", 2, 0x0C, 1, "printer ID"),
+            ("security_type", 2, 0x03, 0, "no security"),
+        ]
+    );
+}
+
+#[test]
+fn prints_bitfields_as_tree() {
+    let dissection = Message::dissect(&AIRPODS_PRO).unwrap().to_string();
+    assert!(dissection.contains("\n    [05..06] device_status: 55\n"));
+    assert!(dissection.contains("\n        ..0. .... = primary_pod (right)\n"));
+    assert!(dissection.contains("\n        ...1 0... = secondary_pod_status (in case)\n"));
+    assert!(dissection.contains("\n        .... 0101 = case_battery (50%)\n"));
+}
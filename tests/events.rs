@@ -0,0 +1,177 @@
+mod common;
+
+use apple_continuity::{
+    events::{Component, Direction, ProximityEvent, ProximityEventStream},
+    messages::ProximityPairMessage,
+    proximity::{DeviceShape, Earbuds, Headphones, ProximityDevice, Status},
+};
+use common::proximity_pairing;
+
+fn device(model: u16) -> ProximityDevice {
+    let data = proximity_pairing(model, 0x00, 0x11);
+    ProximityDevice::from(ProximityPairMessage::decode(&data).unwrap())
+}
+
+fn earbuds(device: &mut ProximityDevice) -> &mut Earbuds {
+    match &mut device.shape {
+        DeviceShape::Earbuds(earbuds) => earbuds,
+        shape => panic!("expected earbuds, got {:?}", shape),
+    }
+}
+
+fn headphones(device: &mut ProximityDevice) -> &mut Headphones {
+    match &mut device.shape {
+        DeviceShape::Headphones(headphones) => headphones,
+        shape => panic!("expected headphones, got {:?}", shape),
+    }
+}
+
+/// The events from pushing `device` and then `device` changed by `change`.
+fn events(
+    device: ProximityDevice,
+    change: impl FnOnce(&mut ProximityDevice),
+) -> Vec<ProximityEvent> {
+    let mut stream = ProximityEventStream::default();
+    assert!(stream.push(device).is_empty());
+
+    let mut changed = device;
+    change(&mut changed);
+    stream.push(changed)
+}
+
+#[test]
+fn reports_case_opened_and_closed() {
+    let airpods = device(0x0E20);
+    assert!(matches!(
+        airpods.shape,
+        DeviceShape::Earbuds(Earbuds { state, .. }) if state.case_open
+    ));
+
+    assert_eq!(
+        events(airpods, |device| earbuds(device).state.case_open = false),
+        [ProximityEvent::CaseClosed]
+    );
+
+    let mut closed = airpods;
+    earbuds(&mut closed).state.case_open = false;
+    assert_eq!(
+        events(closed, |device| earbuds(device).state.case_open = true),
+        [ProximityEvent::CaseOpened]
+    );
+}
+
+#[test]
+fn reports_in_ear_changes() {
+    let mut airpods = device(0x0E20);
+    earbuds(&mut airpods).state.left = Status::InCase;
+
+    assert_eq!(
+        events(airpods, |device| earbuds(device).state.left = Status::InEar),
+        [
+            ProximityEvent::RemovedFromCase(Component::Left),
+            ProximityEvent::InsertedInEar(Component::Left),
+        ]
+    );
+
+    earbuds(&mut airpods).state.right = Status::InEar;
+    assert_eq!(
+        events(airpods, |device| earbuds(device).state.right = Status::Off),
+        [ProximityEvent::RemovedFromEar(Component::Right)]
+    );
+
+    let mut max = device(0x0A20);
+    assert_eq!(
+        events(max, |device| headphones(device).on_head = true),
+        [ProximityEvent::PutOnHead]
+    );
+    headphones(&mut max).on_head = true;
+    assert_eq!(
+        events(max, |device| headphones(device).on_head = false),
+        [ProximityEvent::RemovedFromHead]
+    );
+}
+
+#[test]
+fn reports_charging_changes() {
+    let mut airpods = device(0x0E20);
+    earbuds(&mut airpods).charging_state.case = false;
+    earbuds(&mut airpods).charging_state.left = true;
+
+    assert_eq!(
+        events(airpods, |device| {
+            earbuds(device).charging_state.case = true;
+            earbuds(device).charging_state.left = false;
+        }),
+        [
+            ProximityEvent::ChargingStopped(Component::Left),
+            ProximityEvent::ChargingStarted(Component::Case),
+        ]
+    );
+
+    assert_eq!(
+        events(device(0x0A20), |device| {
+            let charging = headphones(device).charging;
+            headphones(device).charging = !charging;
+        })
+        .len(),
+        1
+    );
+}
+
+#[test]
+fn reports_battery_thresholds() {
+    let airpods = device(0x0E20);
+
+    assert_eq!(
+        events(airpods, |device| earbuds(device).battery.case = Some(40)),
+        [ProximityEvent::BatteryThresholdCrossed {
+            component: Component::Case,
+            threshold: 50,
+            direction: Direction::Falling,
+        }]
+    );
+
+    let mut low = airpods;
+    earbuds(&mut low).battery.right = Some(5);
+    assert_eq!(
+        events(low, |device| earbuds(device).battery.right = Some(20)),
+        [
+            ProximityEvent::BatteryThresholdCrossed {
+                component: Component::Right,
+                threshold: 10,
+                direction: Direction::Rising,
+            },
+            ProximityEvent::BatteryThresholdCrossed {
+                component: Component::Right,
+                threshold: 20,
+                direction: Direction::Rising,
+            },
+        ]
+    );
+
+    // No crossing within a band, or to or from an unavailable level
+    assert!(events(airpods, |device| earbuds(device).battery.left = Some(60)).is_empty());
+    assert!(events(airpods, |device| earbuds(device).battery.case = None).is_empty());
+}
+
+#[test]
+fn reports_lid_open_count_forward_steps() {
+    let mut airpods = device(0x0E20);
+    let lid_events =
+        |device: ProximityDevice, count: u8| events(device, |device| device.lid_open_count = count);
+
+    assert_eq!(
+        lid_events(airpods, 18),
+        [ProximityEvent::LidOpenCountIncremented { count: 18 }]
+    );
+    assert!(lid_events(airpods, 17).is_empty());
+    assert!(lid_events(airpods, 16).is_empty());
+    assert!(lid_events(airpods, 0).is_empty());
+
+    // The counter wraps from 255 to 0
+    airpods.lid_open_count = 255;
+    assert_eq!(
+        lid_events(airpods, 1),
+        [ProximityEvent::LidOpenCountIncremented { count: 1 }]
+    );
+}
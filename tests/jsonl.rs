@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use apple_continuity::{
+    advertisement::{Address, PduType},
+    jsonl::{JsonlReader, JsonlRecord},
+    messages::Message,
+    ErrorKind,
+};
+
+const CAPTURE: &str = r#"{"timestamp": 1700000000.25, "address": "C0:FF:EE:00:00:01", "random_address": true, "rssi": -60, "channel": 37, "data": "0201060AFF4C0010050318112233"}
+
+{"address": "4c-00-00-00-00-03", "data": "0AFF4C0010050301AABBCC"}
+"#;
+
+#[test]
+fn reads_records() {
+    let advertisements: Vec<_> = JsonlReader::new(CAPTURE.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(advertisements.len(), 2);
+
+    let first = &advertisements[0];
+    assert_eq!(first.timestamp, Duration::from_millis(1_700_000_000_250));
+    assert_eq!(
+        first.address,
+        "C0:FF:EE:00:00:01".parse::<Address>().unwrap()
+    );
+    assert!(first.random_address);
+    assert_eq!(first.pdu_type, PduType::AdvInd);
+    assert_eq!(first.rssi, Some(-60));
+    assert_eq!(first.channel, Some(37));
+    assert!(matches!(
+        first.messages().as_slice(),
+        [Message::NearbyInfo(_)]
+    ));
+
+    let second = &advertisements[1];
+    assert_eq!(second.timestamp, Duration::ZERO);
+    assert_eq!(second.address.to_string(), "4C:00:00:00:00:03");
+    assert!(!second.random_address);
+    assert_eq!(second.rssi, None);
+    assert_eq!(second.channel, None);
+}
+
+#[test]
+fn reports_invalid_lines() {
+    let capture = "\n{\"address\": \"C0:FF:EE:00:00:01\", \"data\": \"0201\"}\n\
+                   {\"address\": \"C0:FF:EE:00:00:01\"}\n\
+                   {\"address\": \"C0:FF:EE:00:00:01\", \"data\": \"02XY\"}\n\
+                   {\"address\": \"C0:FF:EE\", \"data\": \"0201\"}\n";
+    let results: Vec<_> = JsonlReader::new(capture.as_bytes()).collect();
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_ok());
+
+    for result in &results[1..] {
+        let error = result.as_ref().unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::CaptureError));
+    }
+
+    // Blank lines still count towards the line number
+    let message = results[1].as_ref().unwrap_err().message.clone().unwrap();
+    assert!(message.starts_with("Line 3:"), "{}", message);
+}
+
+#[test]
+fn round_trips_records() {
+    let advertisement = JsonlReader::new(CAPTURE.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap();
+    let line = serde_json::to_string(&JsonlRecord::from(&advertisement)).unwrap();
+
+    let read = JsonlReader::new(line.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(read.timestamp, advertisement.timestamp);
+    assert_eq!(read.address, advertisement.address);
+    assert_eq!(read.random_address, advertisement.random_address);
+    assert_eq!(read.rssi, advertisement.rssi);
+    assert_eq!(read.channel, advertisement.channel);
+    assert_eq!(read.data, advertisement.data);
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use apple_continuity::{
+    linker::{AddressLinker, LinkerConfig},
+    messages::Message,
+};
+use common::{address, proximity_pairing, seconds};
+
+fn proximity(device_model: u16, device_color: u8, lid_open_count: u8) -> Message {
+    Message::decode(&proximity_pairing(
+        device_model,
+        device_color,
+        lid_open_count,
+    ))
+    .unwrap()
+}
+
+fn handoff(iv: u16) -> Message {
+    let [iv_high, iv_low] = iv.to_be_bytes();
+    let mut data = vec![0x0C, 0x0E, 0x00, iv_high, iv_low, 0x00];
+    data.extend_from_slice(&[0; 10]);
+    Message::decode(&data).unwrap()
+}
+
+fn airplay_target() -> Message {
+    Message::decode(&[0x09, 0x06, 0x03, 0x01, 0xC0, 0xA8, 0x01, 0x02]).unwrap()
+}
+
+#[test]
+fn links_rotated_address() {
+    let mut linker = AddressLinker::new(LinkerConfig::default());
+    assert!(linker
+        .observe(seconds(0), address(1), &proximity(0x0E20, 0x00, 17))
+        .is_none());
+    assert!(linker
+        .observe(seconds(5), address(1), &proximity(0x0E20, 0x00, 17))
+        .is_none());
+
+    let link = linker
+        .observe(seconds(7), address(2), &proximity(0x0E20, 0x00, 18))
+        .unwrap();
+    assert_eq!(link.previous, address(1));
+    assert_eq!(link.current, address(2));
+    assert!(link.confidence >= LinkerConfig::default().min_confidence);
+
+    assert_eq!(linker.device_id(&address(2)), linker.device_id(&address(1)));
+    let device = linker.device_for(&address(2)).unwrap();
+    assert_eq!(device.id, link.device_id);
+    assert_eq!(device.addresses, [address(1), address(2)]);
+    assert_eq!(device.first_seen, seconds(0));
+    assert_eq!(device.last_seen, seconds(7));
+    assert_eq!(device.confidence, link.confidence);
+    assert_eq!(linker.devices().count(), 1);
+
+    // A merged address doesn't start another link
+    assert!(linker
+        .observe(seconds(8), address(2), &proximity(0x0E20, 0x00, 18))
+        .is_none());
+}
+
+#[test]
+fn rejects_mismatched_addresses() {
+    let mut linker = AddressLinker::new(LinkerConfig::default());
+    linker.observe(seconds(0), address(1), &proximity(0x0E20, 0x00, 17));
+
+    // Different model, different color, a lid count that went backwards and a long gap
+    assert!(linker
+        .observe(seconds(1), address(2), &proximity(0x0220, 0x00, 17))
+        .is_none());
+    assert!(linker
+        .observe(seconds(1), address(3), &proximity(0x0E20, 0x01, 17))
+        .is_none());
+    assert!(linker
+        .observe(seconds(1), address(4), &proximity(0x0E20, 0x00, 10))
+        .is_none());
+    assert!(linker
+        .observe(seconds(30), address(5), &proximity(0x0E20, 0x00, 17))
+        .is_none());
+
+    assert_eq!(linker.devices().count(), 5);
+    assert_ne!(linker.device_id(&address(1)), linker.device_id(&address(2)));
+
+    // Handoff IVs that jumped too far
+    let mut linker = AddressLinker::new(LinkerConfig::default());
+    linker.observe(seconds(0), address(1), &handoff(100));
+    assert!(linker
+        .observe(seconds(1), address(2), &handoff(1000))
+        .is_none());
+}
+
+#[test]
+fn bounds_confidence() {
+    let config = LinkerConfig {
+        min_confidence: 0.0,
+        ..LinkerConfig::default()
+    };
+
+    // Matching model, unchanged lid count and no gap is certain
+    let mut linker = AddressLinker::new(config);
+    linker.observe(seconds(0), address(1), &proximity(0x0E20, 0x00, 17));
+    let link = linker
+        .observe(seconds(0), address(2), &proximity(0x0E20, 0x00, 17))
+        .unwrap();
+    assert_eq!(link.confidence, 1.0);
+
+    // Timing alone counts for at most half
+    let mut linker = AddressLinker::new(config);
+    linker.observe(seconds(0), address(1), &airplay_target());
+    let link = linker
+        .observe(seconds(5), address(2), &airplay_target())
+        .unwrap();
+    assert_eq!(link.confidence, 0.25);
+
+    // A Handoff IV that wrapped around is still a small forward step
+    let mut linker = AddressLinker::new(config);
+    linker.observe(seconds(0), address(1), &handoff(u16::MAX - 2));
+    let link = linker
+        .observe(seconds(10), address(2), &handoff(3))
+        .unwrap();
+    assert!((0.0..=1.0).contains(&link.confidence));
+    assert_eq!(link.confidence, 0.75);
+
+    // The default minimum keeps timing only links apart
+    let mut linker = AddressLinker::new(LinkerConfig::default());
+    linker.observe(seconds(0), address(1), &airplay_target());
+    assert!(linker
+        .observe(seconds(0), address(2), &airplay_target())
+        .is_none());
+}
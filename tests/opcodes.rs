@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use apple_continuity::{layout, messages::MessageOpcode};
+
+const README: &str = include_str!("../README.md");
+
+#[test]
+fn parses_opcodes() {
+    assert_eq!(
+        MessageOpcode::try_from(0x0C).unwrap(),
+        MessageOpcode::Handoff
+    );
+    assert!(MessageOpcode::try_from(0x01).is_err());
+    assert_eq!(
+        "proximity_pairing".parse::<MessageOpcode>().unwrap(),
+        MessageOpcode::ProximityPairing
+    );
+    assert_eq!(
+        "AirPlay Target".parse::<MessageOpcode>().unwrap(),
+        MessageOpcode::AirplayTarget
+    );
+    assert_eq!(
+        "0x10".parse::<MessageOpcode>().unwrap(),
+        MessageOpcode::NearbyInfo
+    );
+    assert!("0x01".parse::<MessageOpcode>().is_err());
+    assert!("Find Your".parse::<MessageOpcode>().is_err());
+    assert_eq!(MessageOpcode::Airprint.expected_length(), Some(0x16));
+    assert_eq!(MessageOpcode::ProximityPairing.expected_length(), None);
+}
+
+#[test]
+fn orders_and_hashes_opcodes() {
+    assert!(MessageOpcode::Airprint < MessageOpcode::NearbyInfo);
+    assert_eq!(
+        MessageOpcode::ALL.iter().max(),
+        Some(&MessageOpcode::FindMy)
+    );
+
+    let mut sorted = MessageOpcode::ALL;
+    sorted.reverse();
+    sorted.sort();
+    assert_eq!(sorted, MessageOpcode::ALL);
+
+    let opcodes: HashSet<_> = MessageOpcode::ALL.into_iter().collect();
+    assert_eq!(opcodes.len(), MessageOpcode::ALL.len());
+    assert!(opcodes.contains(&MessageOpcode::Handoff));
+}
+
+#[test]
+fn round_trips_all_opcodes() {
+    for opcode in MessageOpcode::ALL {
+        let value = u8::from(opcode);
+        assert_eq!(MessageOpcode::try_from(value).unwrap(), opcode);
+        assert_eq!(opcode.name().parse::<MessageOpcode>().unwrap(), opcode);
+        assert_eq!(
+            format!("{:#04x}", value).parse::<MessageOpcode>().unwrap(),
+            opcode
+        );
+    }
+
+    let known: Vec<u8> = MessageOpcode::ALL.into_iter().map(u8::from).collect();
+    for value in (0..=u8::MAX).filter(|value| !known.contains(value)) {
+        assert!(MessageOpcode::try_from(value).is_err());
+    }
+}
+
+#[test]
+fn matches_readme_table() {
+    let rows: Vec<Vec<&str>> = README
+        .split("## Opcodes")
+        .nth(1)
+        .unwrap()
+        .lines()
+        .skip_while(|line| !line.starts_with('|'))
+        .take_while(|line| line.starts_with('|'))
+        .skip(2)
+        .map(|line| line.trim_matches('|').split('|').map(str::trim).collect())
+        .collect();
+    assert_eq!(rows.len(), MessageOpcode::ALL.len());
+
+    for (row, opcode) in rows.iter().zip(MessageOpcode::ALL) {
+        let expected_length = match opcode.expected_length() {
+            Some(length) => format!("0x{:02X}", length),
+            None => "Variable".to_string(),
+        };
+        let decoded = if layout::layout(opcode.into()).is_some() {
+            "Yes"
+        } else {
+            ""
+        };
+
+        assert_eq!(
+            row,
+            &[
+                opcode.name(),
+                &format!("0x{:02X}", u8::from(opcode)),
+                &expected_length,
+                decoded,
+            ]
+        );
+    }
+}
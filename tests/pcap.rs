@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use apple_continuity::{
+    advertisement::{Address, Advertisement, PduType},
+    messages::Message,
+    pcap::{channel_from_rf_index, encode_packet, PcapReader, PcapWriter},
+    ErrorKind,
+};
+
+// Synthesized with the PHDR link type: ADV_IND on RF 0, ADV_NONCONN_IND on RF 12 without a
+// valid signal power, a SCAN_REQ, ADV_SCAN_IND on RF 39, SCAN_RSP on RF 0 and a data channel
+// packet. The SCAN_REQ and the data channel packet carry no advertising data.
+const PHDR_PCAP: &[u8] = include_bytes!("fixtures/le_ll_phdr.pcap");
+
+// Synthesized with a plain link-layer interface at nanosecond resolution and a PHDR interface
+// at the default microsecond resolution, holding the same PDU types plus a CONNECT_IND and an
+// interface statistics block.
+const LE_LL_PCAPNG: &[u8] = include_bytes!("fixtures/le_ll.pcapng");
+
+fn read(capture: &[u8]) -> Vec<Advertisement> {
+    PcapReader::new(capture)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn address(text: &str) -> Address {
+    text.parse().unwrap()
+}
+
+#[test]
+fn reads_pcap_with_phdr() {
+    let advertisements = read(PHDR_PCAP);
+    let summary: Vec<_> = advertisements
+        .iter()
+        .map(|advertisement| {
+            (
+                advertisement.pdu_type,
+                advertisement.channel,
+                advertisement.rssi,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            (PduType::AdvInd, Some(37), Some(-60)),
+            (PduType::AdvNonconnInd, Some(38), None),
+            (PduType::AdvScanInd, Some(39), Some(-72)),
+            (PduType::ScanRsp, Some(37), Some(-61)),
+        ]
+    );
+
+    let first = &advertisements[0];
+    assert_eq!(first.timestamp, Duration::from_millis(1500));
+    assert_eq!(first.address, address("C0:FF:EE:00:00:01"));
+    assert!(first.random_address);
+    assert!(matches!(
+        first.messages().as_slice(),
+        [Message::NearbyInfo(_)]
+    ));
+
+    assert_eq!(advertisements[2].address, address("4C:00:00:00:00:03"));
+    assert!(!advertisements[2].random_address);
+    assert_eq!(advertisements[3].manufacturer_data(), None);
+}
+
+#[test]
+fn reads_pcapng_with_both_link_types() {
+    let advertisements = read(LE_LL_PCAPNG);
+    let summary: Vec<_> = advertisements
+        .iter()
+        .map(|advertisement| {
+            (
+                advertisement.pdu_type,
+                advertisement.channel,
+                advertisement.rssi,
+                advertisement.timestamp,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        vec![
+            (PduType::AdvInd, None, None, Duration::from_millis(1500)),
+            (
+                PduType::AdvNonconnInd,
+                Some(38),
+                Some(-55),
+                Duration::from_millis(1750)
+            ),
+            (PduType::AdvScanInd, None, None, Duration::from_millis(2250)),
+            (
+                PduType::ScanRsp,
+                Some(39),
+                Some(-65),
+                Duration::from_secs(3)
+            ),
+        ]
+    );
+
+    assert_eq!(advertisements[1].address, address("C0:FF:EE:00:00:02"));
+    assert!(matches!(
+        advertisements[1].messages().as_slice(),
+        [Message::NearbyInfo(_)]
+    ));
+}
+
+#[test]
+fn maps_rf_indexes_to_channels() {
+    assert_eq!(channel_from_rf_index(0), Some(37));
+    assert_eq!(channel_from_rf_index(1), Some(0));
+    assert_eq!(channel_from_rf_index(11), Some(10));
+    assert_eq!(channel_from_rf_index(12), Some(38));
+    assert_eq!(channel_from_rf_index(13), Some(11));
+    assert_eq!(channel_from_rf_index(38), Some(36));
+    assert_eq!(channel_from_rf_index(39), Some(39));
+    assert_eq!(channel_from_rf_index(40), None);
+}
+
+#[test]
+fn rejects_oversized_records() {
+    // A pcap record header claiming a 4 GiB packet
+    let mut pcap = PHDR_PCAP[..24].to_vec();
+    pcap.extend_from_slice(&[0; 8]);
+    pcap.extend_from_slice(&u32::MAX.to_le_bytes());
+    pcap.extend_from_slice(&u32::MAX.to_le_bytes());
+
+    let error = PcapReader::new(pcap.as_slice())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::CaptureError));
+
+    // A pcapng block of the same size, after the section header block
+    let mut pcapng = LE_LL_PCAPNG[..28].to_vec();
+    pcapng.extend_from_slice(&6u32.to_le_bytes());
+    pcapng.extend_from_slice(&u32::MAX.to_le_bytes());
+
+    let error = PcapReader::new(pcapng.as_slice())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::CaptureError));
+}
+
+#[test]
+fn round_trips_written_advertisements() {
+    let message = Message::decode(&[0x10, 0x05, 0x03, 0x18, 0x11, 0x22, 0x33]).unwrap();
+    let mut written = Vec::new();
+    for (channel, rssi) in [(None, Some(-40)), (Some(38), None), (Some(39), Some(-90))] {
+        let mut advertisement = Advertisement::from_messages(
+            Duration::from_micros(1_250_000),
+            address("C0:FF:EE:00:00:01"),
+            std::slice::from_ref(&message),
+        )
+        .unwrap();
+        advertisement.channel = channel;
+        advertisement.rssi = rssi;
+        written.push(advertisement);
+    }
+
+    let mut writer = PcapWriter::new(Vec::new()).unwrap();
+    for advertisement in &written {
+        writer.write(advertisement).unwrap();
+    }
+    let capture = writer.into_inner();
+
+    // The PHDR of the first packet carries RF index 0 for the default channel 37
+    assert_eq!(capture[24 + 16], 0);
+
+    let read = read(&capture);
+    assert_eq!(read.len(), written.len());
+    for (read, written) in read.iter().zip(&written) {
+        assert_eq!(read.timestamp, written.timestamp);
+        assert_eq!(read.address, written.address);
+        assert_eq!(read.random_address, written.random_address);
+        assert_eq!(read.pdu_type, written.pdu_type);
+        assert_eq!(read.rssi, written.rssi);
+        assert_eq!(read.channel, Some(written.channel.unwrap_or(37)));
+        assert_eq!(read.data, written.data);
+        let messages = read.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].encode(), message.encode());
+    }
+}
+
+#[test]
+fn rejects_oversized_advertising_data() {
+    let message = Message::decode(&[0x10, 0x05, 0x03, 0x18, 0x11, 0x22, 0x33]).unwrap();
+    let address = address("C0:FF:EE:00:00:01");
+
+    // Three Nearby Info messages fill 2 + 2 + 21 bytes, four overflow the 31 byte limit
+    let fits = Advertisement::from_messages(Duration::ZERO, address, &vec![message.clone(); 3]);
+    assert_eq!(fits.unwrap().data.len(), 25);
+
+    let error = Advertisement::from_messages(Duration::ZERO, address, &vec![message.clone(); 4])
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::EncodeError));
+
+    let mut advertisement = Advertisement::from_messages(Duration::ZERO, address, &[]).unwrap();
+    advertisement.data = vec![0; 32];
+    assert!(encode_packet(&advertisement).is_err());
+
+    advertisement.data.truncate(31);
+    advertisement.channel = Some(40);
+    assert!(encode_packet(&advertisement).is_err());
+}
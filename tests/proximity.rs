@@ -0,0 +1,273 @@
+mod common;
+
+use apple_continuity::{
+    messages::ProximityPairMessage,
+    proximity::{
+        BatteryState, ChargingState, DeviceShape, Headphones, ProximityDevice,
+        ProximityDeviceModel, MODELS,
+    },
+};
+use common::proximity_pairing;
+
+fn device(device_model: u16, device_color: u8) -> ProximityDevice {
+    let data = proximity_pairing(device_model, device_color, 0x11);
+    ProximityDevice::from(ProximityPairMessage::decode(&data).unwrap())
+}
+
+/// A device of `device_model` with its status and battery bytes replaced.
+fn device_with(
+    device_model: u16,
+    device_status: u8,
+    battery1: u8,
+    battery2: u8,
+) -> ProximityDevice {
+    let mut data = proximity_pairing(device_model, 0x00, 0x11);
+    data[5] = device_status;
+    data[6] = battery1;
+    data[7] = battery2;
+    ProximityDevice::from(ProximityPairMessage::decode(&data).unwrap())
+}
+
+#[test]
+fn names_airpods_colors() {
+    let airpods = device(0x1420, 0x00);
+    assert_eq!(airpods.model, ProximityDeviceModel::AirpodsProGen2);
+    assert_eq!(airpods.color_name(), Some("White"));
+    assert!(airpods
+        .to_string()
+        .starts_with("AirPods Pro (2nd generation), White"));
+
+    // AirPods earbuds only come in white
+    assert_eq!(device(0x1420, 0x01).color_name(), None);
+    assert_eq!(device(0x0220, 0x00).color_name(), Some("White"));
+}
+
+#[test]
+fn names_airpods_max_colors() {
+    // Codes beyond the early shared palette are kept rather than collapsed to unknown
+    let max = device(0x0A20, 0x11);
+    assert_eq!(max.device_color, 0x11);
+    assert_eq!(max.color_name(), Some("Light Green"));
+    assert_eq!(device(0x0A20, 0x0F).color_name(), Some("Black"));
+    assert_eq!(device(0x0A20, 0x01).color_name(), None);
+}
+
+#[test]
+fn names_beats_headphones_colors() {
+    assert_eq!(device(0x0920, 0x18).color_name(), Some("Shadow Gray"));
+    assert_eq!(device(0x0920, 0x43).color_name(), Some("White Camo"));
+    assert_eq!(device(0x0620, 0x5B).color_name(), Some("Gold"));
+    assert_eq!(device(0x0C20, 0x01).color_name(), Some("Black"));
+}
+
+#[test]
+fn names_beats_earbuds_colors() {
+    // The same code names a different color on each model
+    assert_eq!(device(0x1120, 0x00).color_name(), Some("White"));
+    assert_eq!(device(0x1620, 0x00).color_name(), Some("Black"));
+    assert_eq!(device(0x1220, 0x05).color_name(), Some("Neon Green"));
+    assert_eq!(device(0x1620, 0x05).color_name(), None);
+    assert_eq!(device(0x0B20, 0x0D).color_name(), Some("Sky Blue"));
+    assert_eq!(
+        device(0x0320, 0x17).color_name(),
+        Some("Black with Gold Logo")
+    );
+}
+
+#[test]
+fn leaves_unknown_colors_unnamed() {
+    let unknown = device(0x4242, 0x00);
+    assert_eq!(unknown.model, ProximityDeviceModel::Unknown);
+    assert_eq!(unknown.color_name(), None);
+    assert!(unknown.to_string().starts_with("Unknown, Unknown color"));
+
+    // Models whose codes haven't been collected name nothing
+    assert_eq!(device(0x2920, 0x00).color_name(), None);
+
+    for info in MODELS {
+        for (i, (code, _)) in info.colors.iter().enumerate() {
+            assert!(
+                info.colors[i + 1..].iter().all(|(other, _)| other != code),
+                "{} repeats color code {:#04X}",
+                info.name,
+                code
+            );
+        }
+    }
+}
+
+#[test]
+fn parses_model_names() {
+    for name in ["airpods_pro", "AirPods Pro", " airpods pro "] {
+        assert_eq!(
+            name.parse::<ProximityDeviceModel>().unwrap(),
+            ProximityDeviceModel::AirpodsPro
+        );
+    }
+    assert_eq!(
+        "airpods_pro_gen2_usb_c"
+            .parse::<ProximityDeviceModel>()
+            .unwrap(),
+        ProximityDeviceModel::AirpodsProGen2UsbC
+    );
+
+    assert!("AirpodsPr".parse::<ProximityDeviceModel>().is_err());
+    assert!("unknown".parse::<ProximityDeviceModel>().is_err());
+}
+
+#[test]
+fn maps_battery_nibbles_to_levels() {
+    // With this status the left pod is in the battery byte's high nibble
+    for nibble in 0x0..=0xA {
+        let device = device_with(0x0E20, 0x55, nibble << 4 | nibble, nibble);
+        let battery = device.earbuds().unwrap().battery;
+        let level = Some(nibble as u32 * 10);
+        assert_eq!(
+            battery,
+            BatteryState {
+                case: level,
+                left: level,
+                right: level
+            }
+        );
+    }
+
+    // 0xF is a disconnected component, 0xB to 0xE are undefined
+    for nibble in 0xB..=0xF {
+        let device = device_with(0x0E20, 0x55, nibble << 4 | nibble, nibble);
+        let battery = device.earbuds().unwrap().battery;
+        assert_eq!(
+            battery,
+            BatteryState {
+                case: None,
+                left: None,
+                right: None
+            }
+        );
+    }
+}
+
+#[test]
+fn ignores_charging_without_level() {
+    // Every charging bit set, with the left pod and the case disconnected
+    let device = device_with(0x0E20, 0x55, 0xF8, 0x7F);
+    let earbuds = device.earbuds().unwrap();
+    assert_eq!(earbuds.battery.left, None);
+    assert_eq!(earbuds.battery.right, Some(80));
+    assert_eq!(earbuds.battery.case, None);
+    assert_eq!(
+        earbuds.charging_state,
+        ChargingState {
+            case: false,
+            left: false,
+            right: true
+        }
+    );
+}
+
+#[test]
+fn lists_model_capabilities() {
+    let capabilities = |model: ProximityDeviceModel| model.info().unwrap().capabilities;
+
+    for model in [
+        ProximityDeviceModel::AirpodsPro,
+        ProximityDeviceModel::AirpodsProGen2,
+        ProximityDeviceModel::PowerBeatsPro,
+        ProximityDeviceModel::BeatsStudioBuds,
+    ] {
+        let capabilities = capabilities(model);
+        assert!(
+            capabilities.has_case && !capabilities.single_unit,
+            "{}",
+            model
+        );
+    }
+
+    for model in [
+        ProximityDeviceModel::AirpodsMax,
+        ProximityDeviceModel::AirpodsMaxUsbC,
+        ProximityDeviceModel::BeatsStudio3,
+        ProximityDeviceModel::BeatsSolo3,
+        ProximityDeviceModel::BeatsFlex,
+    ] {
+        let capabilities = capabilities(model);
+        assert!(
+            !capabilities.has_case && capabilities.single_unit,
+            "{}",
+            model
+        );
+    }
+
+    // Earbuds whose case reports no battery
+    let solo_buds = capabilities(ProximityDeviceModel::BeatsSoloBuds);
+    assert!(!solo_buds.has_case && !solo_buds.single_unit);
+
+    assert!(capabilities(ProximityDeviceModel::AirpodsPro).noise_cancellation);
+    assert!(!capabilities(ProximityDeviceModel::Airpods).noise_cancellation);
+    assert!(ProximityDeviceModel::Unknown.info().is_none());
+    assert!(ProximityDeviceModel::Unknown.capabilities().has_case);
+
+    for (i, info) in MODELS.iter().enumerate() {
+        assert!(!(info.capabilities.has_case && info.capabilities.single_unit));
+        assert!(MODELS[i + 1..]
+            .iter()
+            .all(|other| other.model != info.model));
+    }
+}
+
+#[test]
+fn shapes_over_ear_models_as_headphones() {
+    for model in [0x0A20, 0x0920] {
+        // One battery at 70% in the low nibble, charging, and worn
+        let device = device_with(model, 0x02, 0xF7, 0x1F);
+        assert!(device.earbuds().is_none());
+        assert_eq!(
+            device.shape,
+            DeviceShape::Headphones(Headphones {
+                battery: Some(70),
+                charging: true,
+                on_head: true,
+            })
+        );
+
+        // The battery may come in the high nibble instead, with its own charging bit
+        let device = device_with(model, 0x00, 0x6F, 0x1F);
+        assert_eq!(
+            device.headphones(),
+            Some(&Headphones {
+                battery: Some(60),
+                charging: false,
+                on_head: false,
+            })
+        );
+
+        let device = device_with(model, 0x00, 0xFF, 0x3F);
+        assert_eq!(
+            device.headphones(),
+            Some(&Headphones {
+                battery: None,
+                charging: false,
+                on_head: false,
+            })
+        );
+    }
+}
+
+#[test]
+fn hides_case_of_caseless_earbuds() {
+    // Beats Solo Buds with a case level, case charging bit and lid bit set, which caseless
+    // models may still send
+    let device = device_with(0x2620, 0x55, 0x98, 0x75);
+    let earbuds = device.earbuds().unwrap();
+    assert_eq!(earbuds.battery.case, None);
+    assert!(!earbuds.charging_state.case);
+    assert!(!earbuds.state.case_open);
+    assert_eq!(earbuds.battery.left, Some(90));
+    assert_eq!(earbuds.battery.right, Some(80));
+
+    let airpods = device_with(0x0E20, 0x55, 0x98, 0x75);
+    let earbuds = airpods.earbuds().unwrap();
+    assert_eq!(earbuds.battery.case, Some(50));
+    assert!(earbuds.charging_state.case);
+    assert!(earbuds.state.case_open);
+}
@@ -0,0 +1,123 @@
+use apple_continuity::{
+    proximity::{ProximityDeviceModel, MODELS},
+    ranging::{
+        Calibration, FittedCurve, LogDistance, PathLossModel, RangeEstimator, RangingConfig, Zone,
+        DEFAULT_TX_POWER,
+    },
+};
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-3,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn computes_log_distance() {
+    let free_space = LogDistance::default();
+    assert_close(free_space.distance(-59.0, -59.0), 1.0);
+    assert_close(free_space.distance(-79.0, -59.0), 10.0);
+    assert_close(free_space.distance(-39.0, -59.0), 0.1);
+
+    let indoor = LogDistance { exponent: 4.0 };
+    assert_close(indoor.distance(-79.0, -59.0), 10f64.sqrt());
+}
+
+#[test]
+fn computes_fitted_curve() {
+    let curve = FittedCurve::default();
+    assert_close(curve.distance(-59.0, -59.0), 0.42093 + 0.54992);
+    assert_close(
+        curve.distance(-70.0, -59.0),
+        0.42093 * (70.0f64 / 59.0).powf(6.9476) + 0.54992,
+    );
+
+    // Stronger than the 1m RSSI uses the short range approximation
+    assert_close(curve.distance(-50.0, -59.0), (50.0f64 / 59.0).powi(10));
+}
+
+#[test]
+fn classifies_zones() {
+    let config = RangingConfig::default();
+    assert_eq!(config.zone(0.1), Zone::Immediate);
+    assert_eq!(config.zone(0.5), Zone::Immediate);
+    assert_eq!(config.zone(0.51), Zone::Near);
+    assert_eq!(config.zone(4.0), Zone::Near);
+    assert_eq!(config.zone(4.01), Zone::Far);
+
+    let config = RangingConfig {
+        immediate: 1.0,
+        near: 2.0,
+        ..config
+    };
+    assert_eq!(config.zone(0.8), Zone::Immediate);
+    assert_eq!(config.zone(3.0), Zone::Far);
+}
+
+#[test]
+fn smooths_rssi() {
+    let mut estimator = RangeEstimator::default();
+    assert!(estimator.estimate().is_none());
+
+    // The first sample is taken as is, later ones move a quarter of the way
+    let estimate = estimator.push(-59, DEFAULT_TX_POWER);
+    assert_close(estimate.rssi, -59.0);
+    assert_close(estimate.distance, 1.0);
+    assert_eq!(estimate.zone, Zone::Near);
+
+    assert_close(estimator.push(-79, DEFAULT_TX_POWER).rssi, -64.0);
+    assert_close(estimator.push(-79, DEFAULT_TX_POWER).rssi, -67.75);
+
+    let estimate = estimator.estimate().unwrap();
+    assert_close(estimate.rssi, -67.75);
+    assert_close(estimate.distance, 10f64.powf(8.75 / 20.0));
+
+    estimator.reset();
+    assert!(estimator.estimate().is_none());
+    assert_close(estimator.push(-40, -50).rssi, -40.0);
+
+    let mut unsmoothed = RangeEstimator::new(
+        LogDistance::default(),
+        RangingConfig {
+            smoothing: 1.0,
+            ..RangingConfig::default()
+        },
+    );
+    unsmoothed.push(-59, DEFAULT_TX_POWER);
+    assert_close(unsmoothed.push(-79, DEFAULT_TX_POWER).distance, 10.0);
+}
+
+#[test]
+fn falls_back_without_calibrated_models() {
+    let calibration = Calibration::default();
+    assert!(calibration.models.is_empty());
+    assert_eq!(calibration.default_tx_power, DEFAULT_TX_POWER);
+    for info in MODELS {
+        assert_eq!(calibration.tx_power(info.model), DEFAULT_TX_POWER);
+    }
+
+    let calibration = Calibration {
+        default_tx_power: -65,
+        ..Calibration::default()
+    };
+    assert_eq!(calibration.tx_power(ProximityDeviceModel::AirpodsPro), -65);
+}
+
+#[test]
+fn replaces_calibrated_models() {
+    let calibration = Calibration::default()
+        .with_model(ProximityDeviceModel::AirpodsPro, -62)
+        .with_model(ProximityDeviceModel::AirpodsMax, -55)
+        .with_model(ProximityDeviceModel::AirpodsPro, -65);
+
+    assert_eq!(calibration.models.len(), 2);
+    assert_eq!(calibration.tx_power(ProximityDeviceModel::AirpodsPro), -65);
+    assert_eq!(calibration.tx_power(ProximityDeviceModel::AirpodsMax), -55);
+    assert_eq!(
+        calibration.tx_power(ProximityDeviceModel::BeatsStudio3),
+        DEFAULT_TX_POWER
+    );
+}
@@ -0,0 +1,105 @@
+use apple_continuity::{
+    dissect::Field,
+    messages::{CustomMessage, Message, MessageOpcode},
+    registry::Registry,
+    Error,
+};
+
+// A Find My message: opcode 0x12, length 0x02, status and a key byte
+const FIND_MY: [u8; 4] = [0x12, 0x02, 0x10, 0x2A];
+const NEARBY_INFO: [u8; 7] = [0x10, 0x05, 0x03, 0x1C, 0x11, 0x22, 0x33];
+
+fn find_my(data: &[u8]) -> Result<Message, Error> {
+    let fields = vec![
+        Field::new("opcode", data, 0..1),
+        Field::new("length", data, 1..2),
+        Field::new("status", data, 2..3).interpreted("separated"),
+        Field::new("key_bits", data, 3..4),
+    ];
+
+    Ok(Message::Custom(
+        CustomMessage::new("Find My", data)?.fields(fields),
+    ))
+}
+
+#[test]
+fn decodes_registered_opcodes() {
+    let mut registry = Registry::default();
+    assert!(registry.decode(&FIND_MY).is_err());
+    assert!(registry.decode(&[]).is_err());
+
+    registry.register(0x12, find_my);
+    let message = registry.decode(&FIND_MY).unwrap();
+    assert_eq!(message.name(), "Find My");
+    assert_eq!(message.opcode(), Some(MessageOpcode::FindMy));
+    assert_eq!(message.encode(), FIND_MY);
+    assert_eq!(
+        message.hex_dump().lines().nth(2),
+        Some("02  10                       status")
+    );
+
+    assert!(matches!(
+        registry.decode(&NEARBY_INFO),
+        Ok(Message::NearbyInfo(_))
+    ));
+    assert!(registry.unregister(0x10));
+    assert!(registry.decode(&NEARBY_INFO).is_err());
+}
+
+#[test]
+fn overrides_built_in_decoders() {
+    let mut registry = Registry::empty();
+    registry.register(0x10, |data: &[u8]| {
+        Ok(Message::Custom(CustomMessage::new("Nearby Info v2", data)?))
+    });
+
+    let message = registry.decode(&NEARBY_INFO).unwrap();
+    assert_eq!(message.name(), "Nearby Info v2");
+    assert_eq!(registry.opcodes().collect::<Vec<_>>(), vec![0x10]);
+}
+
+// Restores the default registry when the test ends, even if it fails
+struct InstalledRegistry;
+
+impl InstalledRegistry {
+    fn install(registry: Registry) -> InstalledRegistry {
+        registry.install();
+        InstalledRegistry
+    }
+}
+
+impl Drop for InstalledRegistry {
+    fn drop(&mut self) {
+        Registry::default().install();
+    }
+}
+
+#[test]
+fn installs_registry_for_message_decode() {
+    assert!(Message::decode(&FIND_MY).is_err());
+
+    let mut registry = Registry::current();
+    registry.register(0x12, find_my);
+    // A decoder that decodes through the installed registry itself
+    registry.register(0x13, |data: &[u8]| {
+        let mut inner = data.to_vec();
+        inner[0] = 0x12;
+        Message::decode(&inner)
+    });
+    let _installed = InstalledRegistry::install(registry);
+
+    let dissection = Message::dissect(&FIND_MY).unwrap();
+    assert_eq!(dissection.name, "Find My");
+    assert_eq!(
+        dissection.children[2].interpreted.as_deref(),
+        Some("separated")
+    );
+    assert!(matches!(
+        Message::decode(&NEARBY_INFO),
+        Ok(Message::NearbyInfo(_))
+    ));
+    assert_eq!(
+        Message::decode(&[0x13, 0x02, 0x10, 0x2A]).unwrap().name(),
+        "Find My"
+    );
+}
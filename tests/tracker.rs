@@ -0,0 +1,128 @@
+mod common;
+
+use apple_continuity::{
+    advertisement::{Address, Advertisement},
+    events::Component,
+    messages::Message,
+    tracker::{DeviceTracker, TrackerConfig, TrackerEvent},
+};
+use common::{address, seconds, AIRPODS_PRO};
+
+const NEARBY_INFO: [u8; 7] = [0x10, 0x05, 0x03, 0x18, 0x11, 0x22, 0x33];
+const NEARBY_INFO_UNLOCKED: [u8; 7] = [0x10, 0x05, 0x07, 0x18, 0x11, 0x22, 0x33];
+
+fn message(data: &[u8]) -> Message {
+    Message::decode(data).unwrap()
+}
+
+fn kinds(events: &[TrackerEvent]) -> Vec<(&'static str, Address)> {
+    events
+        .iter()
+        .map(|event| match event {
+            TrackerEvent::Appeared(device) => ("appeared", device.address),
+            TrackerEvent::Updated(device) => ("updated", device.address),
+            TrackerEvent::Lost(device) => ("lost", device.address),
+        })
+        .collect()
+}
+
+#[test]
+fn reports_appeared_and_updated() {
+    let mut tracker = DeviceTracker::new(TrackerConfig::default());
+
+    let events = tracker.ingest(seconds(0), address(1), Some(-60), message(&NEARBY_INFO));
+    assert_eq!(kinds(&events), [("appeared", address(1))]);
+
+    // Repeating the same message only refreshes the device
+    let events = tracker.ingest(seconds(1), address(1), Some(-70), message(&NEARBY_INFO));
+    assert!(events.is_empty());
+
+    let events = tracker.ingest(seconds(2), address(1), None, message(&NEARBY_INFO_UNLOCKED));
+    assert_eq!(kinds(&events), [("updated", address(1))]);
+
+    let TrackerEvent::Updated(device) = &events[0] else {
+        unreachable!()
+    };
+    assert_eq!(device.first_seen, seconds(0));
+    assert_eq!(device.last_seen, seconds(2));
+    assert_eq!(device.message_count, 3);
+    assert_eq!(device.message_types().collect::<Vec<_>>(), [0x10]);
+    assert_eq!(device.average_rssi(), Some(-65.0));
+    assert_eq!(device.latest_messages[&0x10].encode(), NEARBY_INFO_UNLOCKED);
+
+    let events = tracker.ingest(seconds(2), address(2), None, message(&NEARBY_INFO));
+    assert_eq!(kinds(&events), [("appeared", address(2))]);
+    assert_eq!(tracker.devices().count(), 2);
+}
+
+#[test]
+fn keeps_configured_rssi_history() {
+    let mut tracker = DeviceTracker::new(TrackerConfig {
+        rssi_history: 2,
+        ..TrackerConfig::default()
+    });
+
+    for (second, rssi) in [(0, -50), (1, -60), (2, -80)] {
+        tracker.ingest(
+            seconds(second),
+            address(1),
+            Some(rssi),
+            message(&NEARBY_INFO),
+        );
+    }
+
+    let device = tracker.get(&address(1)).unwrap();
+    let history: Vec<_> = device
+        .rssi_history
+        .iter()
+        .map(|sample| sample.rssi)
+        .collect();
+    assert_eq!(history, [-60, -80]);
+    assert_eq!(device.average_rssi(), Some(-70.0));
+}
+
+#[test]
+fn tracks_proximity_pairing() {
+    let mut tracker = DeviceTracker::new(TrackerConfig::default());
+    let advertisement =
+        Advertisement::from_messages(seconds(5), address(1), &[message(&AIRPODS_PRO)]).unwrap();
+
+    let events = tracker.ingest_advertisement(&advertisement);
+    assert_eq!(kinds(&events), [("appeared", address(1))]);
+
+    let device = tracker.get(&address(1)).unwrap();
+    assert!(device.proximity.is_some());
+    assert_eq!(device.battery.samples(Component::Left).len(), 1);
+}
+
+#[test]
+fn expires_after_timeout() {
+    let mut tracker = DeviceTracker::new(TrackerConfig {
+        timeout: seconds(10),
+        ..TrackerConfig::default()
+    });
+
+    tracker.ingest(seconds(0), address(1), None, message(&NEARBY_INFO));
+    tracker.ingest(seconds(5), address(2), None, message(&NEARBY_INFO));
+
+    // A device heard exactly one timeout ago is kept
+    assert!(tracker.expire(seconds(10)).is_empty());
+
+    // Ingesting reports lost devices before the message itself
+    let events = tracker.ingest(seconds(11), address(3), None, message(&NEARBY_INFO));
+    assert_eq!(
+        kinds(&events),
+        [("lost", address(1)), ("appeared", address(3))]
+    );
+    assert!(tracker.get(&address(1)).is_none());
+
+    let events = tracker.expire(seconds(30));
+    let mut lost = kinds(&events);
+    lost.sort();
+    assert_eq!(lost, [("lost", address(2)), ("lost", address(3))]);
+    assert_eq!(tracker.devices().count(), 0);
+
+    // A lost device appears again when it is next heard
+    let events = tracker.ingest(seconds(31), address(1), None, message(&NEARBY_INFO));
+    assert_eq!(kinds(&events), [("appeared", address(1))]);
+}
@@ -0,0 +1,99 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use apple_continuity::{
+    layout::LAYOUTS,
+    messages::MessageOpcode,
+    wireshark::{lua_dissector, PROTOCOL},
+};
+
+#[test]
+fn declares_every_layout_field() {
+    let lua = lua_dissector();
+
+    for layout in LAYOUTS {
+        let key = layout.key();
+        assert!(
+            lua.contains(&format!("    [0x{:02X}] = {{\n", layout.opcode)),
+            "no layout entry for {}",
+            layout.name
+        );
+
+        for field in layout.fields {
+            let filter = format!("\"{}.{}.{}\"", PROTOCOL, key, field.name);
+            assert!(lua.contains(&filter), "{} is not declared", filter);
+
+            for bits in field.bits {
+                let filter = format!("\"{}.{}.{}.{}\"", PROTOCOL, key, field.name, bits.name);
+                let mask = format!("0x{:02X})", bits.mask);
+                assert!(
+                    lua.lines()
+                        .any(|line| line.contains(&filter) && line.ends_with(&mask)),
+                    "{} is not declared with mask {}",
+                    filter,
+                    mask
+                );
+            }
+        }
+    }
+
+    assert!(lua.contains(
+        "f.airprint_connection_info_security_type = \
+         ProtoField.uint8(\"continuity.airprint.connection_info.security_type\", \
+         \"Security type\", base.DEC, nil, 0x03)"
+    ));
+}
+
+#[test]
+fn dispatches_on_opcodes() {
+    let lua = lua_dissector();
+
+    for opcode in MessageOpcode::ALL {
+        assert!(lua.contains(&format!(
+            "    [0x{:02X}] = \"{}\",\n",
+            opcode as u8,
+            opcode.name()
+        )));
+    }
+
+    assert!(lua.contains("local layout = layouts[opcode]"));
+    assert!(lua.contains(
+        "DissectorTable.get(\"btcommon.eir_ad.manufacturer_company_id\"):add(0x004C, continuity)"
+    ));
+}
+
+#[test]
+fn compiles_with_luac() {
+    let lua = lua_dissector();
+
+    // Only checked where a Lua compiler is installed
+    let Some(mut child) = ["luac", "luac5.4", "luac5.3", "luac5.2", "luac5.1"]
+        .into_iter()
+        .find_map(|luac| {
+            Command::new(luac)
+                .args(["-p", "-"])
+                .stdin(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .ok()
+        })
+    else {
+        eprintln!("luac not found, skipping");
+        return;
+    };
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(lua.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}